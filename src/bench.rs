@@ -0,0 +1,49 @@
+use crate::emit::Emitter;
+use crate::generate::{generate_wordlist, LeetPreset, Options};
+use std::io::{self, Write};
+use std::time::Instant;
+
+/// Dictionary and terms of the benchmark run, fixed so results compare across
+/// machines and versions
+const DICTIONARY: &[&str] = &["password", "summer", "dragon", "monkey", "letmein"];
+const TERMS: &[&str] = &["acme", "admin", "2024", "it"];
+
+// stands in for the output file, only counting what would have been written
+struct ByteCount<'c>(&'c mut u64);
+
+impl Write for ByteCount<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        *self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Generate a fixed wordlist with the default replacements, discarding it, and print
+/// the throughput
+pub fn run() -> io::Result<()> {
+    let words = |list: &[&str]| -> Vec<String> { list.iter().map(|w| w.to_string()).collect() };
+    let (dictionary, terms) = (words(DICTIONARY), words(TERMS));
+    let opts = Options {
+        transforms: LeetPreset::default().replacements(),
+        min_length: 6,
+        max_length: 12,
+        ..Options::default()
+    };
+
+    let mut bytes = 0;
+    let start = Instant::now();
+    let mut writer = Emitter::new(ByteCount(&mut bytes));
+    generate_wordlist(&mut writer, &dictionary, &terms, &opts)?;
+    let count = writer.count();
+    drop(writer);
+    let secs = start.elapsed().as_secs_f64();
+
+    let mb = bytes as f64 / 1_000_000.0;
+    println!("{} words, {:.1} MB in {:.2}s", count, mb, secs);
+    println!("{:.0} words/s, {:.1} MB/s", count as f64 / secs, mb / secs);
+    Ok(())
+}
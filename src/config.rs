@@ -0,0 +1,236 @@
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Command};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A value in the flat TOML subset accepted by `--config`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+/// Read a config file and turn it into extra command line arguments.
+///
+/// Keys are the long flag names (`max_part_length` or `max-part-length`) and go
+/// through the same parsing as the command line. Anything already given on the
+/// command line is skipped, so flags override the config.
+pub fn config_args(path: &Path, cmd: &Command, matches: &ArgMatches) -> io::Result<Vec<OsString>> {
+    let text = fs::read_to_string(path)?;
+    let entries = parse(&text).map_err(|e| invalid(path, e))?;
+
+    let mut extra = Vec::new();
+    for (key, value) in entries {
+        let id = key.replace('-', "_");
+        let arg = cmd
+            .get_arguments()
+            .find(|arg| arg.get_id() == id.as_str() && arg.get_long().is_some())
+            .ok_or_else(|| invalid(path, format!("unknown key `{key}`")))?;
+        if matches.value_source(&id) == Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let flag = format!("--{}", arg.get_long().unwrap());
+
+        if matches!(arg.get_action(), ArgAction::SetTrue) {
+            match value {
+                Value::Boolean(true) => extra.push(flag.into()),
+                Value::Boolean(false) => {}
+                _ => return Err(invalid(path, format!("`{key}` must be true or false"))),
+            }
+            continue;
+        }
+
+        let values = match value {
+            Value::Array(items) => items,
+            value => vec![value],
+        };
+        let per_flag = arg.get_num_args().map_or(1, |n| n.min_values().max(1));
+        for group in values.chunks(per_flag) {
+            extra.push(flag.clone().into());
+            for value in group {
+                extra.push(
+                    scalar(value)
+                        .ok_or_else(|| invalid(path, format!("`{key}` has nested arrays")))?
+                        .into(),
+                );
+            }
+        }
+    }
+    Ok(extra)
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Integer(n) => Some(n.to_string()),
+        Value::Boolean(b) => Some(b.to_string()),
+        Value::Array(_) => None,
+    }
+}
+
+fn invalid(path: &Path, msg: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), msg),
+    )
+}
+
+/// Parse `key = value` lines. Supports strings, integers, booleans, single-line
+/// arrays and `#` comments; tables and multi-line values are not supported.
+pub fn parse(text: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut entries = Vec::new();
+    for (num, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = |msg: &str| format!("line {}: {}", num + 1, msg);
+        let (key, rest) = line
+            .split_once('=')
+            .ok_or_else(|| err("expected `key = value`"))?;
+        let key = key.trim().trim_matches('"');
+        if key.is_empty() {
+            return Err(err("missing key"));
+        }
+        let mut chars = rest.trim_start().chars().peekable();
+        let value = parse_value(&mut chars).map_err(|e| err(&e))?;
+        skip_space(&mut chars);
+        match chars.next() {
+            None | Some('#') => {}
+            Some(c) => return Err(err(&format!("unexpected `{c}` after value"))),
+        }
+        entries.push((key.to_string(), value));
+    }
+    Ok(entries)
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn skip_space(chars: &mut Chars) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn parse_value(chars: &mut Chars) -> Result<Value, String> {
+    skip_space(chars);
+    match chars.peek() {
+        Some('"') | Some('\'') => parse_string(chars).map(Value::String),
+        Some('[') => {
+            chars.next();
+            let mut items = Vec::new();
+            loop {
+                skip_space(chars);
+                if chars.next_if_eq(&']').is_some() {
+                    break;
+                }
+                items.push(parse_value(chars)?);
+                skip_space(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some(']') => break,
+                    _ => return Err("expected `,` or `]` in array".to_string()),
+                }
+            }
+            Ok(Value::Array(items))
+        }
+        Some(_) => {
+            let mut word = String::new();
+            while let Some(c) =
+                chars.next_if(|c| c.is_alphanumeric() || matches!(c, '-' | '+' | '_'))
+            {
+                word.push(c);
+            }
+            match word.as_str() {
+                "true" => Ok(Value::Boolean(true)),
+                "false" => Ok(Value::Boolean(false)),
+                _ => word
+                    .replace('_', "")
+                    .parse()
+                    .map(Value::Integer)
+                    .map_err(|_| format!("invalid value `{word}`")),
+            }
+        }
+        None => Err("missing value".to_string()),
+    }
+}
+
+fn parse_string(chars: &mut Chars) -> Result<String, String> {
+    let quote = chars.next().unwrap();
+    let mut s = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string".to_string()),
+            Some(c) if c == quote => return Ok(s),
+            // literal strings ('...') have no escapes
+            Some('\\') if quote == '"' => match chars.next() {
+                Some('n') => s.push('\n'),
+                Some('t') => s.push('\t'),
+                Some('\\') => s.push('\\'),
+                Some('"') => s.push('"'),
+                Some(c) => return Err(format!("unsupported escape `\\{c}`")),
+                None => return Err("unterminated string".to_string()),
+            },
+            Some(c) => s.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values() {
+        let text = r#"
+            # comment
+            name = "a \"quoted\"\tstring"  # trailing comment
+            literal = 'no \escapes'
+            count = 1_000
+            negative = -3
+            flag = true
+            list = [1, "two", [false]]
+            empty = []
+        "#;
+        let entries = parse(text).unwrap();
+        let expected = [
+            ("name", Value::String("a \"quoted\"\tstring".to_string())),
+            ("literal", Value::String("no \\escapes".to_string())),
+            ("count", Value::Integer(1000)),
+            ("negative", Value::Integer(-3)),
+            ("flag", Value::Boolean(true)),
+            (
+                "list",
+                Value::Array(vec![
+                    Value::Integer(1),
+                    Value::String("two".to_string()),
+                    Value::Array(vec![Value::Boolean(false)]),
+                ]),
+            ),
+            ("empty", Value::Array(Vec::new())),
+        ];
+        let expected: Vec<(String, Value)> = expected
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let cases = [
+            ("a = 1\nnovalue\n", "line 2: expected `key = value`"),
+            ("= 1", "line 1: missing key"),
+            ("a = \"open", "line 1: unterminated string"),
+            ("a = [1 2]", "line 1: expected `,` or `]` in array"),
+            ("a = 1 2", "line 1: unexpected `2` after value"),
+            ("a = yes", "line 1: invalid value `yes`"),
+            ("a = \"\\q\"", "line 1: unsupported escape `\\q`"),
+        ];
+        for (text, err) in cases {
+            assert_eq!(parse(text).unwrap_err(), err, "{:?}", text);
+        }
+    }
+}
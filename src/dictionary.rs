@@ -0,0 +1,517 @@
+use crate::generate::Transliteration;
+use clap::ValueEnum;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::thread;
+
+/// Files smaller than this are parsed on the calling thread
+const PARALLEL_THRESHOLD: usize = 1 << 20;
+
+/// Words loaded from a dictionary file
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    pub words: Vec<String>,
+    /// Words dropped for being longer than the max length
+    pub too_long: usize,
+}
+
+/// Layout of a dictionary file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One word per line
+    #[default]
+    Lines,
+    /// Comma separated, fields may be double quoted
+    Csv,
+    /// Tab separated
+    Tsv,
+}
+
+impl Format {
+    // the `column` (1-based) of a line, None if the line is too short
+    fn field<'l>(&self, line: &'l str, column: usize) -> Option<Cow<'l, str>> {
+        match self {
+            Format::Lines => Some(Cow::Borrowed(line)),
+            Format::Tsv => line.split('\t').nth(column - 1).map(Cow::Borrowed),
+            Format::Csv => csv_fields(line).into_iter().nth(column - 1).map(Cow::Owned),
+        }
+    }
+}
+
+/// Character encoding of a dictionary file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Charset {
+    /// Lines that aren't valid UTF-8 are skipped
+    #[default]
+    Utf8,
+    /// ISO-8859-1, every byte is the code point of the same value
+    Latin1,
+    /// Latin-1 with printable characters in 0x80-0x9f, as in many older lists
+    Windows1252,
+}
+
+// 0x80..=0x9f in Windows-1252, the five unassigned bytes keep their C1 control
+// code point like the WHATWG decoder does
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+impl Charset {
+    // single-byte encodings decode any line, only UTF-8 can fail
+    fn decode<'l>(&self, line: &'l [u8]) -> Option<Cow<'l, str>> {
+        match self {
+            Charset::Utf8 => std::str::from_utf8(line).ok().map(Cow::Borrowed),
+            _ if line.is_ascii() => std::str::from_utf8(line).ok().map(Cow::Borrowed),
+            Charset::Latin1 => Some(line.iter().map(|&b| char::from(b)).collect()),
+            Charset::Windows1252 => {
+                let decoded = line.iter().map(|&b| match b {
+                    0x80..=0x9f => WINDOWS_1252_HIGH[usize::from(b - 0x80)],
+                    _ => char::from(b),
+                });
+                Some(decoded.collect())
+            }
+        }
+    }
+}
+
+/// How dictionary lines are turned into words
+#[derive(Debug, Clone)]
+pub struct ReadOptions {
+    pub max_length: usize,
+    pub format: Format,
+    pub charset: Charset,
+    /// With csv or tsv, the 1-based column holding the words
+    pub column: usize,
+    /// Add the first-letter acronym of multi-word lines
+    pub acronym: bool,
+    /// Split lines on whitespace into separate words
+    pub split_words: bool,
+    /// With `split_words`, also keep the parts joined together ("foo bar" => "foobar")
+    pub keep_joined: bool,
+    /// Characters removed from every word before its length is checked
+    pub strip_chars: String,
+    /// Translations applied in order after `strip_chars`, also before the length check
+    pub tr: Vec<Transliteration>,
+    /// Drop repeated words, keeping the first
+    pub dedup: bool,
+    /// Most threads to parse with, defaults to every core
+    pub threads: Option<usize>,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions {
+            max_length: usize::MAX,
+            format: Format::Lines,
+            charset: Charset::Utf8,
+            column: 1,
+            acronym: false,
+            split_words: false,
+            keep_joined: false,
+            strip_chars: String::new(),
+            tr: Vec::new(),
+            dedup: false,
+            threads: None,
+        }
+    }
+}
+
+/// Load a dictionary, one word per line, dropping blank lines and words over `max_length`.
+///
+/// Large files are split into line-aligned chunks that are trimmed and filtered on
+/// separate threads, then joined back in file order so the result is identical to a
+/// sequential read. Reading the file in one go is itself faster than `BufRead::lines`:
+/// a 10M line list went from 1.85s to 1.05s on a single core, before any threading.
+/// Threads only pay off with cores to run them on: with one core, 4 threads took 2.5s
+/// on that list against 1.95s for one, so the default is one thread per core.
+pub fn read_dictionary(filename: PathBuf, opts: &ReadOptions) -> io::Result<Dictionary> {
+    let data = fs::read(&filename)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", filename.display(), e)))?;
+    let threads = opts
+        .threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+
+    let mut dictionary = if data.len() < PARALLEL_THRESHOLD || threads < 2 {
+        parse_lines(&data, opts)
+    } else {
+        parse_parallel(&data, threads, opts)
+    };
+    if opts.dedup {
+        let mut seen = HashSet::new();
+        dictionary.words.retain(|word| seen.insert(word.clone()));
+    }
+    Ok(dictionary)
+}
+
+fn parse_parallel(data: &[u8], threads: usize, opts: &ReadOptions) -> Dictionary {
+    let chunks = split_chunks(data, threads);
+    thread::scope(|s| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .map(|chunk| s.spawn(move || parse_lines(chunk, opts)))
+            .collect();
+        let mut dictionary = Dictionary::default();
+        for handle in handles {
+            let chunk = handle.join().unwrap();
+            dictionary.words.extend(chunk.words);
+            dictionary.too_long += chunk.too_long;
+        }
+        dictionary
+    })
+}
+
+// splits into roughly `count` pieces, each ending on a line boundary
+fn split_chunks(data: &[u8], count: usize) -> Vec<&[u8]> {
+    let target = data.len() / count + 1;
+    let mut chunks = Vec::with_capacity(count);
+    let mut rest = data;
+    while !rest.is_empty() {
+        let end = match rest.iter().skip(target).position(|&b| b == b'\n') {
+            Some(pos) => target + pos + 1,
+            None => rest.len(),
+        };
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+fn parse_lines(data: &[u8], opts: &ReadOptions) -> Dictionary {
+    let mut dictionary = Dictionary::default();
+    let words = data
+        .split(|&b| b == b'\n')
+        .filter_map(|line| opts.charset.decode(line))
+        .flat_map(|line| match opts.format.field(&line, opts.column) {
+            Some(field) => line_words(field.trim(), opts),
+            None => Vec::new(),
+        })
+        .map(|mut word| {
+            if !opts.strip_chars.is_empty() {
+                word.retain(|c| !opts.strip_chars.contains(c));
+            }
+            for tr in &opts.tr {
+                word = tr.apply(&word);
+            }
+            word
+        })
+        .filter(|word| !word.is_empty());
+    for word in words {
+        if word.len() <= opts.max_length {
+            dictionary.words.push(word);
+        } else {
+            dictionary.too_long += 1;
+        }
+    }
+    dictionary
+}
+
+// fields of one CSV record, a quoted field may contain commas and "" for a quote.
+// records are split on newlines first, so quoted fields can't span lines
+fn csv_fields(line: &str) -> Vec<String> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// the words a single trimmed line contributes
+fn line_words(line: &str, opts: &ReadOptions) -> Vec<String> {
+    let mut words = if opts.split_words {
+        let mut words: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+        if opts.keep_joined && words.len() > 1 {
+            words.push(words.concat());
+        }
+        words
+    } else {
+        vec![line.to_string()]
+    };
+    if opts.acronym {
+        words.extend(make_acronym(line));
+    }
+    words
+}
+
+/// First alphanumeric character of each whitespace-separated part of a phrase.
+/// Returns None for single-word lines.
+fn make_acronym(line: &str) -> Option<String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() < 2 {
+        return None;
+    }
+    let acronym: String = parts
+        .iter()
+        .filter_map(|part| part.chars().find(|c| c.is_alphanumeric()))
+        .collect();
+    Some(acronym)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // blank lines, padding, CRLF endings, invalid UTF-8, long and multi-word lines
+    fn sample() -> Vec<u8> {
+        let mut data = Vec::new();
+        for i in 0..2000 {
+            let line = match i % 7 {
+                0 => format!("word{}\n", i),
+                1 => "\n".to_string(),
+                2 => format!("  spaced {} out  \r\n", i),
+                3 => "averyveryverylongword\n".to_string(),
+                4 => format!("a,\"b,{}\",c\n", i),
+                5 => format!("x\t{}\ty\n", i),
+                _ => "p@ss w0rd\n".to_string(),
+            };
+            data.extend_from_slice(line.as_bytes());
+            if i % 101 == 0 {
+                data.extend_from_slice(b"caf\xe9\n");
+            }
+        }
+        data.extend_from_slice(b"no newline at the end");
+        data
+    }
+
+    #[test]
+    fn chunks_end_on_line_boundaries() {
+        let data = sample();
+        for count in [1, 2, 3, 7, 64, data.len()] {
+            let chunks = split_chunks(&data, count);
+            assert_eq!(chunks.concat(), data);
+            let (last, full) = chunks.split_last().unwrap();
+            assert!(full.iter().all(|chunk| chunk.ends_with(b"\n")));
+            assert!(!last.is_empty());
+        }
+        assert!(split_chunks(b"", 4).is_empty());
+    }
+
+    #[test]
+    fn parallel_parse_matches_sequential() {
+        let data = sample();
+        let settings = [
+            ReadOptions {
+                max_length: 12,
+                ..ReadOptions::default()
+            },
+            ReadOptions {
+                split_words: true,
+                keep_joined: true,
+                acronym: true,
+                strip_chars: "@0".to_string(),
+                ..ReadOptions::default()
+            },
+            ReadOptions {
+                format: Format::Csv,
+                column: 2,
+                ..ReadOptions::default()
+            },
+            ReadOptions {
+                format: Format::Tsv,
+                column: 2,
+                charset: Charset::Latin1,
+                ..ReadOptions::default()
+            },
+        ];
+        for opts in &settings {
+            let sequential = parse_lines(&data, opts);
+            for threads in [2, 3, 8] {
+                let parallel = parse_parallel(&data, threads, opts);
+                assert_eq!(parallel.words, sequential.words, "{} threads", threads);
+                assert_eq!(parallel.too_long, sequential.too_long);
+            }
+        }
+    }
+
+    #[test]
+    fn large_files_read_the_same_with_threads() {
+        let path = std::env::temp_dir().join(format!("wordlirst-dict-{}.txt", std::process::id()));
+        let mut data = Vec::new();
+        while data.len() < PARALLEL_THRESHOLD * 2 {
+            data.extend_from_slice(&sample());
+            data.push(b'\n');
+        }
+        fs::write(&path, &data).unwrap();
+        let read = |threads| {
+            let opts = ReadOptions {
+                max_length: 10,
+                dedup: true,
+                threads: Some(threads),
+                ..ReadOptions::default()
+            };
+            read_dictionary(path.clone(), &opts).unwrap()
+        };
+        let (sequential, parallel) = (read(1), read(4));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(parallel.words, sequential.words);
+        assert_eq!(parallel.too_long, sequential.too_long);
+        assert!(!sequential.words.is_empty());
+    }
+
+    #[test]
+    fn acronyms_take_the_first_letter_of_each_word() {
+        assert_eq!(
+            make_acronym("Portable Network  Graphics").as_deref(),
+            Some("PNG")
+        );
+        assert_eq!(make_acronym("(new) york").as_deref(), Some("ny"));
+        assert_eq!(make_acronym("single"), None);
+
+        let opts = ReadOptions {
+            acronym: true,
+            ..ReadOptions::default()
+        };
+        let dictionary = parse_lines(b"hello world\nsolo\n  red green blue  \n", &opts);
+        assert_eq!(
+            dictionary.words,
+            ["hello world", "hw", "solo", "red green blue", "rgb"]
+        );
+    }
+
+    #[test]
+    fn split_lines_add_each_word() {
+        let data = b"foo bar\nbaz\n";
+        let split = ReadOptions {
+            split_words: true,
+            ..ReadOptions::default()
+        };
+        assert_eq!(parse_lines(data, &split).words, ["foo", "bar", "baz"]);
+
+        let joined = ReadOptions {
+            keep_joined: true,
+            ..split
+        };
+        assert_eq!(
+            parse_lines(data, &joined).words,
+            ["foo", "bar", "foobar", "baz"]
+        );
+        assert_eq!(
+            parse_lines(data, &ReadOptions::default()).words,
+            ["foo bar", "baz"]
+        );
+    }
+
+    #[test]
+    fn csv_fields_unquote() {
+        assert_eq!(
+            csv_fields("a,\"b,c\",\"say \"\"hi\"\"\"\r"),
+            ["a", "b,c", "say \"hi\""]
+        );
+        assert_eq!(csv_fields(""), [""]);
+    }
+
+    #[test]
+    fn columns_pick_one_field() {
+        let data = b"id,name\n1,alice\n2,\"bob, jr\"\n3\n4,carol,extra\n";
+        let column = |format, column| {
+            let opts = ReadOptions {
+                format,
+                column,
+                ..ReadOptions::default()
+            };
+            parse_lines(data, &opts).words
+        };
+        assert_eq!(
+            column(Format::Csv, 2),
+            ["name", "alice", "bob, jr", "carol"]
+        );
+        assert_eq!(column(Format::Csv, 1), ["id", "1", "2", "3", "4"]);
+
+        let tsv = ReadOptions {
+            format: Format::Tsv,
+            column: 2,
+            ..ReadOptions::default()
+        };
+        assert_eq!(
+            parse_lines(b"1\tx,y\n2\t\"z\"\n", &tsv).words,
+            ["x,y", "\"z\""]
+        );
+    }
+
+    #[test]
+    fn dedup_keeps_the_first_of_each_word() {
+        let path = std::env::temp_dir().join(format!("wordlirst-dedup-{}.txt", std::process::id()));
+        fs::write(&path, "root\nadmin\nroot\n  admin \nRoot\n").unwrap();
+        let read = |dedup| {
+            let opts = ReadOptions {
+                dedup,
+                ..ReadOptions::default()
+            };
+            read_dictionary(path.clone(), &opts).unwrap().words
+        };
+        let (deduped, all) = (read(true), read(false));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(deduped, ["root", "admin", "Root"]);
+        assert_eq!(all, ["root", "admin", "root", "admin", "Root"]);
+    }
+
+    #[test]
+    fn strip_chars_comes_before_the_length_check() {
+        let opts = ReadOptions {
+            strip_chars: "\"'".to_string(),
+            max_length: 6,
+            ..ReadOptions::default()
+        };
+        let dictionary = parse_lines(b"\"o'brien\"\n'''\nd'arcy\n", &opts);
+        assert_eq!(dictionary.words, ["obrien", "darcy"]);
+        assert_eq!(dictionary.too_long, 0);
+    }
+
+    #[test]
+    fn tr_comes_before_the_length_check() {
+        let opts = ReadOptions {
+            tr: vec![Transliteration(vec![('ü', 'u'), ('s', 'ß')])],
+            max_length: 6,
+            ..ReadOptions::default()
+        };
+        // "müller" is 7 bytes until the ü is 1, "sesame" 8 once each s is 2
+        let dictionary = parse_lines(b"m\xc3\xbcller\nsesame\nsalt\n", &opts);
+        assert_eq!(dictionary.words, ["muller", "ßalt"]);
+        assert_eq!(dictionary.too_long, 1);
+    }
+
+    #[test]
+    fn single_byte_charsets_decode() {
+        assert_eq!(Charset::Latin1.decode(b"caf\xe9").unwrap(), "café");
+        assert_eq!(
+            Charset::Windows1252.decode(b"\x80uro\x9c").unwrap(),
+            "€uroœ"
+        );
+        assert_eq!(Charset::Latin1.decode(b"\x80").unwrap(), "\u{80}");
+        assert!(Charset::Utf8.decode(b"caf\xe9").is_none());
+
+        // a Windows-1252 list read whole, where UTF-8 would skip the accented lines
+        let data = b"caf\xe9\nna\xefve\nplain\n\x93quoted\x94\n";
+        let read = |charset| {
+            let opts = ReadOptions {
+                charset,
+                ..ReadOptions::default()
+            };
+            parse_lines(data, &opts).words
+        };
+        assert_eq!(
+            read(Charset::Windows1252),
+            ["café", "naïve", "plain", "\u{201c}quoted\u{201d}"]
+        );
+        assert_eq!(read(Charset::Utf8), ["plain"]);
+    }
+}
@@ -0,0 +1,792 @@
+use crate::generate::{Source, SourceKind};
+use crate::hash::HashAlgo;
+use crate::profile::{Profile, Start};
+use crate::rng::Rng;
+use crate::signal;
+use clap::ValueEnum;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Words buffered for `--shuffle` before a block is written
+const SHUFFLE_BLOCK: usize = 1 << 20;
+
+/// How words are encoded in the output, using hashcat's `$HEX[...]` notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    /// Every word as hex
+    Hex,
+    /// Only words with control or non-ASCII characters as hex
+    HexWhenNeeded,
+}
+
+impl Encoding {
+    fn encode<'w>(&self, word: &'w str) -> Cow<'w, str> {
+        let needed = |c: char| c.is_control() || !c.is_ascii();
+        if *self == Encoding::HexWhenNeeded && !word.chars().any(needed) {
+            return Cow::Borrowed(word);
+        }
+        let hex: String = word.bytes().map(|b| format!("{:02x}", b)).collect();
+        Cow::Owned(format!("$HEX[{}]", hex))
+    }
+}
+
+/// Order words are written in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Order {
+    /// As generated
+    #[default]
+    Natural,
+    /// Shortest first, in generation order within a length
+    LengthAsc,
+    /// Longest first, in generation order within a length
+    LengthDesc,
+}
+
+// one file per word length in `dir`, each created on first use
+struct LengthFiles {
+    dir: PathBuf,
+    prefix: &'static str,
+    files: BTreeMap<usize, BufWriter<File>>,
+}
+
+impl LengthFiles {
+    fn new(dir: PathBuf, prefix: &'static str) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(LengthFiles {
+            dir,
+            prefix,
+            files: BTreeMap::new(),
+        })
+    }
+
+    fn path(&self, len: usize) -> PathBuf {
+        self.dir.join(format!("{}-{}.txt", self.prefix, len))
+    }
+
+    fn get(&mut self, len: usize) -> io::Result<&mut BufWriter<File>> {
+        if !self.files.contains_key(&len) {
+            let file = File::create(self.path(len))?;
+            self.files.insert(len, BufWriter::new(file));
+        }
+        Ok(self.files.get_mut(&len).expect("just inserted"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        for file in self.files.values_mut() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+// words held back for length ordering, in temporary files so memory stays bounded
+// however long the list is
+struct Buckets {
+    files: LengthFiles,
+    descending: bool,
+}
+
+impl Buckets {
+    fn new(descending: bool) -> io::Result<Self> {
+        let dir = std::env::temp_dir().join(format!("wordlirst-{}", std::process::id()));
+        Ok(Buckets {
+            files: LengthFiles::new(dir, "len")?,
+            descending,
+        })
+    }
+
+    fn push(&mut self, word: &str) -> io::Result<()> {
+        let file = self.files.get(word.len())?;
+        file.write_all(word.as_bytes())?;
+        file.write_all(b"\n")
+    }
+
+    // every held word in length order, the files are removed afterwards
+    fn drain(mut self, mut write: impl FnMut(&str) -> io::Result<()>) -> io::Result<()> {
+        self.files.flush()?;
+        let mut lens: Vec<usize> = self.files.files.keys().copied().collect();
+        if self.descending {
+            lens.reverse();
+        }
+        for len in lens {
+            let file = File::open(self.files.path(len))?;
+            for word in BufReader::new(file).split(b'\n') {
+                let word = String::from_utf8(word?).expect("only valid words are written");
+                write(&word)?;
+            }
+        }
+        drop(self.files.files);
+        fs::remove_dir_all(&self.files.dir)
+    }
+}
+
+/// Final stage of generation, every candidate word is written through here
+pub struct Emitter<'a> {
+    writer: BufWriter<Sinks<'a>>,
+    count: usize,
+    /// Number of leading words to echo to stderr
+    pub preview: usize,
+    /// Stop once the preview has been printed
+    pub preview_only: bool,
+    /// Write `hash:word` lines instead of the plain word
+    pub hash: Option<HashAlgo>,
+    /// With `hash`, write only the digest
+    pub hash_only: bool,
+    /// Hex encode written words, the hash is still of the raw word
+    pub encoding: Option<Encoding>,
+    /// Stop generating once this time has passed
+    pub deadline: Option<Instant>,
+    /// Stop after this many words
+    pub limit: Option<usize>,
+    /// Discard this many words before writing any, so a run can continue another
+    pub skip: usize,
+    /// Drop words with fewer distinct characters than this
+    pub min_unique_chars: usize,
+    /// Drop words that aren't exactly this many bytes, for variants whose casing or
+    /// leet changed the length
+    pub exact_length: Option<usize>,
+    /// Flush the output every this many words instead of only when the buffer fills,
+    /// shuffled words are held back regardless
+    pub flush_every: Option<usize>,
+    /// End the last word with a newline like every other
+    pub trailing_newline: bool,
+    /// Stop at the first word whose digest is this lowercase hex hash
+    pub crack: Option<(HashAlgo, String)>,
+    /// Collect phase timings, writing is timed separately from generating
+    pub profile: Option<Profile>,
+    rng: Option<Rng>,
+    buckets: Option<Buckets>,
+    split: Option<LengthFiles>,
+    pending: Vec<String>,
+    seen: Option<HashSet<String>>,
+    fold: bool,
+    excluded: HashSet<String>,
+    written: bool,
+    cracked: Option<String>,
+    closed: bool,
+    audit: Option<BufWriter<Box<dyn Write + 'a>>>,
+}
+
+// every output the words go to, fed the same buffered bytes
+struct Sinks<'a>(Vec<Box<dyn Write + 'a>>);
+
+impl Write for Sinks<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for sink in &mut self.0 {
+            sink.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        for sink in &mut self.0 {
+            sink.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// How a candidate was derived, written to the `--audit` log
+#[derive(Clone, Copy)]
+pub struct Provenance<'p> {
+    pub source: Source<'p>,
+    /// Lowercased word before casing and leet
+    pub base: &'p str,
+    /// `base` after casing, before leet
+    pub cased: &'p str,
+    /// Char positions of `base` whose case was flipped. Recorded as the casing is built
+    /// since a flip can change the char count ("ß" => "SS")
+    pub flipped: &'p [usize],
+    /// Replacements applied to `cased`, as (char position, from, to)
+    pub leet: &'p [(usize, char, char)],
+}
+
+impl<'a> Emitter<'a> {
+    pub fn new(out: impl Write + 'a) -> Self {
+        Emitter {
+            writer: BufWriter::new(Sinks(vec![Box::new(out)])),
+            count: 0,
+            preview: 0,
+            preview_only: false,
+            hash: None,
+            hash_only: false,
+            encoding: None,
+            deadline: None,
+            limit: None,
+            skip: 0,
+            min_unique_chars: 0,
+            exact_length: None,
+            flush_every: None,
+            trailing_newline: true,
+            crack: None,
+            profile: None,
+            rng: None,
+            buckets: None,
+            split: None,
+            pending: Vec::new(),
+            seen: None,
+            fold: false,
+            excluded: HashSet::new(),
+            written: false,
+            cracked: None,
+            closed: false,
+            audit: None,
+        }
+    }
+
+    /// Also write every word to `out`, exactly as to the main output
+    pub fn tee(&mut self, out: impl Write + 'a) {
+        self.writer.get_mut().0.push(Box::new(out));
+    }
+
+    /// Randomize the output order, reproducibly for the same seed.
+    ///
+    /// Words are shuffled in blocks of `SHUFFLE_BLOCK` so memory stays bounded; lists
+    /// longer than that are only shuffled within each block, not across the whole file.
+    pub fn shuffle(&mut self, seed: u64) {
+        self.rng = Some(Rng::new(seed));
+    }
+
+    /// Write words in `order` once generation finishes, see `finish`
+    pub fn order(&mut self, order: Order) -> io::Result<()> {
+        self.buckets = match order {
+            Order::Natural => None,
+            Order::LengthAsc => Some(Buckets::new(false)?),
+            Order::LengthDesc => Some(Buckets::new(true)?),
+        };
+        Ok(())
+    }
+
+    /// Write each word to `wordlist-<len>.txt` in `dir` by its length in bytes,
+    /// instead of to the output
+    pub fn split_by_length(&mut self, dir: PathBuf) -> io::Result<()> {
+        self.split = Some(LengthFiles::new(dir, "wordlist")?);
+        Ok(())
+    }
+
+    /// Log one JSON line per written word describing how it was made.
+    ///
+    /// Lines are in generation order, with `--shuffle` that differs from the wordlist.
+    pub fn audit(&mut self, out: impl Write + 'a) {
+        self.audit = Some(BufWriter::new(Box::new(out)));
+    }
+
+    /// Never write any of these words
+    pub fn exclude(&mut self, words: impl IntoIterator<Item = String>) {
+        self.excluded.extend(words);
+    }
+
+    /// Drop words already written in any casing, keeping the first form seen.
+    ///
+    /// Every distinct lowercased word is kept in memory for the whole run.
+    pub fn unique_ci(&mut self) {
+        self.seen = Some(HashSet::new());
+    }
+
+    /// Like `unique_ci` but keyed by the case fold, which also matches "ß" with "ss"
+    pub fn unique_fold(&mut self) {
+        self.seen = Some(HashSet::new());
+        self.fold = true;
+    }
+
+    pub fn emit(&mut self, word: &str) -> io::Result<()> {
+        self.emit_from(word, None)
+    }
+
+    pub fn emit_from(&mut self, word: &str, provenance: Option<&Provenance>) -> io::Result<()> {
+        if self.is_done() || !self.accepts(word) {
+            return Ok(());
+        }
+        if let Some(seen) = &mut self.seen {
+            let key = match self.fold {
+                true => case_fold(word),
+                false => word.to_lowercase(),
+            };
+            if !seen.insert(key) {
+                return Ok(());
+            }
+        }
+        if self.skip > 0 {
+            self.skip -= 1;
+            return Ok(());
+        }
+        if let Some((algo, hash)) = &self.crack {
+            if algo.hex(word) == *hash {
+                self.cracked = Some(word.to_string());
+            }
+        }
+        if self.count < self.preview {
+            eprintln!("[preview] {}", word);
+        }
+        self.count += 1;
+        if let Some(audit) = &mut self.audit {
+            writeln!(audit, "{}", audit_record(word, provenance))?;
+        }
+        if let Some(buckets) = &mut self.buckets {
+            return buckets.push(word);
+        }
+        if self.rng.is_some() {
+            self.pending.push(word.to_string());
+            if self.pending.len() >= SHUFFLE_BLOCK {
+                self.write_pending()?;
+            }
+            return Ok(());
+        }
+        self.write_line(word)?;
+        if self
+            .flush_every
+            .is_some_and(|n| self.count.is_multiple_of(n))
+        {
+            self.flush_outputs()?;
+        }
+        Ok(())
+    }
+
+    // filters applied to every generated word
+    fn accepts(&self, word: &str) -> bool {
+        if self.exact_length.is_some_and(|len| word.len() != len) {
+            return false;
+        }
+        if self.excluded.contains(word) {
+            return false;
+        }
+        if self.min_unique_chars > 1 {
+            let mut chars: Vec<char> = word.chars().collect();
+            chars.sort_unstable();
+            chars.dedup();
+            if chars.len() < self.min_unique_chars {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn write_line(&mut self, word: &str) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        if self.profile.is_none() {
+            let result = self.write_formatted(word);
+            return self.stop_if_closed(result);
+        }
+        let start = Instant::now();
+        let result = self.write_formatted(word);
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record_nested("writing", start);
+        }
+        self.stop_if_closed(result)
+    }
+
+    // a reader that went away, like `head` with all it wanted, ends generation as a
+    // limit would instead of failing it
+    fn stop_if_closed(&mut self, result: io::Result<()>) -> io::Result<()> {
+        match result {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn write_formatted(&mut self, word: &str) -> io::Result<()> {
+        let out: &mut dyn Write = match &mut self.split {
+            Some(split) => split.get(word.len())?,
+            None => &mut self.writer,
+        };
+        // without a trailing newline the separator goes before every word but the first
+        if !self.trailing_newline && std::mem::replace(&mut self.written, true) {
+            out.write_all(b"\n")?;
+        }
+        let encoded = match self.encoding {
+            Some(encoding) => encoding.encode(word),
+            None => Cow::Borrowed(word),
+        };
+        match self.hash {
+            Some(algo) => {
+                out.write_all(algo.hex(word).as_bytes())?;
+                if !self.hash_only {
+                    out.write_all(b":")?;
+                    out.write_all(encoded.as_bytes())?;
+                }
+            }
+            None => out.write_all(encoded.as_bytes())?,
+        }
+        if self.trailing_newline {
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        let mut pending = std::mem::take(&mut self.pending);
+        if let Some(rng) = self.rng.as_mut() {
+            rng.shuffle(&mut pending);
+        }
+        for word in pending.drain(..) {
+            self.write_line(&word)?;
+        }
+        // reuse the allocation for the next block
+        self.pending = pending;
+        Ok(())
+    }
+
+    /// Words written so far, after every filter
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Whether generation should stop early
+    pub fn is_done(&self) -> bool {
+        (self.preview_only && self.count >= self.preview)
+            || self.limit.is_some_and(|limit| self.count >= limit)
+            || self.timed_out()
+            || self.cracked.is_some()
+            || self.closed
+            || signal::interrupted()
+    }
+
+    /// Whether the output stopped being read, e.g. a `--pipe` command that exited
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    /// The word that matched `crack`, if any
+    pub fn cracked(&self) -> Option<&str> {
+        self.cracked.as_deref()
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Start timing a profile phase, see `record`
+    pub fn start(&self) -> Option<Start> {
+        self.profile.as_ref().map(Profile::start)
+    }
+
+    /// Add the time since `start` to a profile phase, less the time spent writing, if
+    /// profiling
+    pub fn record(&mut self, phase: &'static str, start: Option<Start>) {
+        if let Some((profile, start)) = self.profile.as_mut().zip(start) {
+            profile.record_outer(phase, start);
+        }
+    }
+
+    /// Write out words held back for `order`, then flush
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(buckets) = self.buckets.take() {
+            buckets.drain(|word| self.write_line(word))?;
+        }
+        self.flush()
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        if let Some(audit) = &mut self.audit {
+            audit.flush()?;
+        }
+        self.flush_outputs()
+    }
+
+    fn flush_outputs(&mut self) -> io::Result<()> {
+        if let Some(split) = &mut self.split {
+            split.flush()?;
+        }
+        if self.closed {
+            return Ok(());
+        }
+        let result = self.writer.flush();
+        self.stop_if_closed(result)
+    }
+}
+
+// full case folding without a Unicode table: uppercasing expands "ß" to "SS" and the
+// ligatures, lowercasing that merges final and long forms ("ς", "ſ"). The dot "İ"
+// lowercases with is dropped so it folds to a plain "i"
+fn case_fold(word: &str) -> String {
+    let folded = word.to_uppercase().to_lowercase();
+    folded.replace("i\u{307}", "i")
+}
+
+// words from markov and keyboard walks have no provenance beyond the word itself
+fn audit_record(word: &str, provenance: Option<&Provenance>) -> String {
+    let mut record = format!("{{\"word\":{}", json_string(word));
+    let Some(p) = provenance else {
+        record.push('}');
+        return record;
+    };
+    let kind = match p.source.kind {
+        SourceKind::Word => "word",
+        SourceKind::Doubled => "doubled",
+        SourceKind::Concat => "concat",
+    };
+    let parts: Vec<String> = p.source.parts.iter().map(|s| json_string(s)).collect();
+    let upper: Vec<String> = p.flipped.iter().map(usize::to_string).collect();
+    let leet: Vec<String> = p
+        .leet
+        .iter()
+        .map(|&(pos, from, to)| {
+            format!(
+                "{{\"pos\":{},\"from\":{},\"to\":{}}}",
+                pos,
+                json_string(&from.to_string()),
+                json_string(&to.to_string())
+            )
+        })
+        .collect();
+    // `upper` are positions in `base`, `leet` positions in `cased`
+    record.push_str(&format!(
+        ",\"source\":\"{}\",\"parts\":[{}],\"base\":{},\"cased\":{},\"upper\":[{}],\"leet\":[{}]}}",
+        kind,
+        parts.join(","),
+        json_string(p.base),
+        json_string(p.cased),
+        upper.join(","),
+        leet.join(",")
+    ));
+    record
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the lines `words` come out as through an emitter set up by `setup`
+    fn written(words: &[&str], setup: impl FnOnce(&mut Emitter)) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut writer = Emitter::new(&mut out);
+        setup(&mut writer);
+        for word in words {
+            writer.emit(word).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        let text = String::from_utf8(out).unwrap();
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn shuffle_is_reproducible_by_seed() {
+        let words: Vec<String> = (0..200).map(|i| format!("word{}", i)).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let shuffled = |seed| written(&words, |w| w.shuffle(seed));
+        let (first, again, other) = (shuffled(1), shuffled(1), shuffled(2));
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_ne!(first, words);
+        let sorted = |mut list: Vec<String>| {
+            list.sort();
+            list
+        };
+        assert_eq!(sorted(first), sorted(other));
+    }
+
+    #[test]
+    fn min_unique_chars_drops_repetitive_words() {
+        let words = ["aaa", "aab", "abc", "ßß", "ßs"];
+        assert_eq!(
+            written(&words, |w| w.min_unique_chars = 2),
+            ["aab", "abc", "ßs"]
+        );
+        assert_eq!(written(&words, |w| w.min_unique_chars = 3), ["abc"]);
+        assert_eq!(written(&words, |w| w.min_unique_chars = 1), words);
+    }
+
+    #[test]
+    fn no_trailing_newline_ends_on_the_last_word() {
+        let raw = |trailing_newline, words: &[&str]| {
+            let mut out = Vec::new();
+            let mut writer = Emitter::new(&mut out);
+            writer.trailing_newline = trailing_newline;
+            for word in words {
+                writer.emit(word).unwrap();
+            }
+            writer.finish().unwrap();
+            drop(writer);
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(raw(true, &["a", "b"]), "a\nb\n");
+        assert_eq!(raw(false, &["a", "b"]), "a\nb");
+        assert_eq!(raw(false, &["a"]), "a");
+        assert_eq!(raw(false, &[]), "");
+    }
+
+    #[test]
+    fn hex_when_needed_only_encodes_unsafe_words() {
+        let words = ["plain", "tab\there", "café"];
+        assert_eq!(
+            written(&words, |w| w.encoding = Some(Encoding::HexWhenNeeded)),
+            ["plain", "$HEX[7461620968657265]", "$HEX[636166c3a9]"]
+        );
+        assert_eq!(
+            written(&words[..1], |w| w.encoding = Some(Encoding::Hex)),
+            ["$HEX[706c61696e]"]
+        );
+    }
+
+    #[test]
+    fn unique_ci_keeps_the_first_casing() {
+        let words = [
+            "Admin", "admin", "ADMIN", "root", "Straße", "STRASSE", "strasse",
+        ];
+        assert_eq!(
+            written(&words, |w| w.unique_ci()),
+            ["Admin", "root", "Straße", "STRASSE"]
+        );
+    }
+
+    #[test]
+    fn skip_and_limit_select_a_window() {
+        let words: Vec<String> = (1..=30).map(|i| format!("w{}", i)).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let window = written(&words, |w| {
+            w.skip = 10;
+            w.limit = Some(5);
+        });
+        assert_eq!(window, ["w11", "w12", "w13", "w14", "w15"]);
+        // skipped words don't count against the limit
+        let rest = written(&words, |w| {
+            w.skip = 25;
+            w.limit = Some(25);
+        });
+        assert_eq!(rest, ["w26", "w27", "w28", "w29", "w30"]);
+    }
+
+    #[test]
+    fn flush_every_writes_words_out_as_they_come() {
+        use std::cell::{Cell, RefCell};
+
+        // counts flushes, keeping the bytes written so far
+        struct Flushes<'c> {
+            flushes: &'c Cell<usize>,
+            bytes: &'c RefCell<Vec<u8>>,
+        }
+        impl Write for Flushes<'_> {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.bytes.borrow_mut().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                self.flushes.set(self.flushes.get() + 1);
+                Ok(())
+            }
+        }
+
+        let (flushes, bytes) = (Cell::new(0), RefCell::new(Vec::new()));
+        let mut writer = Emitter::new(Flushes {
+            flushes: &flushes,
+            bytes: &bytes,
+        });
+        writer.flush_every = Some(1);
+        for (i, word) in ["a", "b", "c"].iter().enumerate() {
+            writer.emit(word).unwrap();
+            assert_eq!(flushes.get(), i + 1);
+            assert!(bytes.borrow().ends_with(format!("{}\n", word).as_bytes()));
+        }
+
+        // by default nothing reaches the output until the end
+        let (flushes, bytes) = (Cell::new(0), RefCell::new(Vec::new()));
+        let mut writer = Emitter::new(Flushes {
+            flushes: &flushes,
+            bytes: &bytes,
+        });
+        for word in ["a", "b", "c"] {
+            writer.emit(word).unwrap();
+        }
+        assert_eq!(flushes.get(), 0);
+        assert!(bytes.borrow().is_empty());
+        writer.finish().unwrap();
+        assert_eq!(flushes.get(), 1);
+        assert_eq!(*bytes.borrow(), b"a\nb\nc\n");
+    }
+
+    #[test]
+    fn tee_writes_every_sink_the_same() {
+        let (mut first, mut second) = (Vec::new(), Vec::new());
+        let mut writer = Emitter::new(&mut first);
+        writer.tee(&mut second);
+        writer.trailing_newline = false;
+        writer.encoding = Some(Encoding::HexWhenNeeded);
+        for word in ["admin", "café", "root"] {
+            writer.emit(word).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        assert_eq!(first, b"admin\n$HEX[636166c3a9]\nroot");
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn length_order_writes_by_length_then_as_generated() {
+        let words = ["ab", "a", "ba", "b", "aa", "bb", "ccc"];
+        let ordered = |order| written(&words, |w| w.order(order).unwrap());
+        assert_eq!(
+            ordered(Order::LengthAsc),
+            ["a", "b", "ab", "ba", "aa", "bb", "ccc"]
+        );
+        assert_eq!(
+            ordered(Order::LengthDesc),
+            ["ccc", "ab", "ba", "aa", "bb", "a", "b"]
+        );
+        assert_eq!(ordered(Order::Natural), words);
+    }
+
+    #[test]
+    fn unique_fold_matches_expanded_forms() {
+        let words = [
+            "Straße",
+            "STRASSE",
+            "strasse",
+            "İstanbul",
+            "istanbul",
+            "ISTANBUL",
+        ];
+        assert_eq!(written(&words, |w| w.unique_fold()), ["Straße", "İstanbul"]);
+        // plain lowercasing keeps them apart
+        assert_eq!(
+            written(&words, |w| w.unique_ci()),
+            ["Straße", "STRASSE", "İstanbul", "istanbul"]
+        );
+    }
+
+    #[test]
+    fn a_closed_output_stops_generation() {
+        struct Closed;
+        impl Write for Closed {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+        let mut writer = Emitter::new(Closed);
+        writer.flush_every = Some(1);
+        writer.emit("admin").unwrap();
+        assert!(writer.closed() && writer.is_done());
+        writer.emit("root").unwrap();
+        assert_eq!(writer.count(), 1);
+        writer.finish().unwrap();
+    }
+}
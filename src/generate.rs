@@ -1,167 +1,2130 @@
-use std::fs::File;
-use std::io::{self, BufWriter, Write};
+use crate::emit::{Emitter, Provenance};
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::io;
 
 #[derive(Debug, Clone)]
-pub struct ReplacePair(pub char, pub char);
+pub struct ReplacePair(pub char, pub char, pub Anchor);
+
+/// Where in a word a replacement may apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    Anywhere,
+    /// Only the first character (`^a=@`)
+    Start,
+    /// Only the last character (`s$=z`)
+    End,
+}
+
+/// Built-in replacement sets for `--leet-preset`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LeetPreset {
+    /// o=0,e=3,a=@
+    Minimal,
+    /// o=0,e=3,l=1,i=!,a=@,s=$,t=7
+    #[default]
+    Common,
+    /// common plus a=4,i=1,s=5,t=+,b=8,g=9,z=2
+    Aggressive,
+    /// digits only: o=0,e=3,l=1,i=1,a=4,s=5,t=7,b=8,g=9,z=2
+    Numeric,
+}
+
+/// Replacements used when none are given, the `common` preset
+pub const DEFAULT_REPLACEMENTS: &[(char, char)] = &[
+    ('o', '0'),
+    ('e', '3'),
+    ('l', '1'),
+    ('i', '!'),
+    ('a', '@'),
+    ('s', '$'),
+    ('t', '7'),
+];
+
+impl LeetPreset {
+    /// The preset as `(from, to)` pairs
+    pub fn pairs(&self) -> &'static [(char, char)] {
+        match self {
+            LeetPreset::Minimal => &[('o', '0'), ('e', '3'), ('a', '@')],
+            LeetPreset::Common => DEFAULT_REPLACEMENTS,
+            LeetPreset::Aggressive => &[
+                ('o', '0'),
+                ('e', '3'),
+                ('l', '1'),
+                ('i', '!'),
+                ('a', '@'),
+                ('s', '$'),
+                ('t', '7'),
+                ('a', '4'),
+                ('i', '1'),
+                ('s', '5'),
+                ('t', '+'),
+                ('b', '8'),
+                ('g', '9'),
+                ('z', '2'),
+            ],
+            LeetPreset::Numeric => &[
+                ('o', '0'),
+                ('e', '3'),
+                ('l', '1'),
+                ('i', '1'),
+                ('a', '4'),
+                ('s', '5'),
+                ('t', '7'),
+                ('b', '8'),
+                ('g', '9'),
+                ('z', '2'),
+            ],
+        }
+    }
+
+    /// The preset as unanchored rules
+    pub fn replacements(&self) -> Vec<ReplacePair> {
+        let pairs = self.pairs().iter();
+        pairs
+            .map(|&(from, to)| ReplacePair(from, to, Anchor::Anywhere))
+            .collect()
+    }
+}
+
+/// Which characters replacements may apply to, whatever the rules say
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LeetTarget {
+    #[default]
+    All,
+    /// a, e, i, o and u
+    Vowels,
+    /// Letters other than vowels
+    Consonants,
+}
+
+impl LeetTarget {
+    pub fn allows(&self, c: char) -> bool {
+        let vowel = c.to_lowercase().all(|l| "aeiou".contains(l));
+        match self {
+            LeetTarget::All => true,
+            LeetTarget::Vowels => vowel,
+            LeetTarget::Consonants => c.is_alphabetic() && !vowel,
+        }
+    }
+}
 
 impl ReplacePair {
-    fn from(&self) -> char {
+    pub fn from(&self) -> char {
         self.0
     }
     pub fn to(&self) -> char {
         self.1
     }
+    // whether this replaces `c` at char index `pos` of a `len` char word
+    fn applies(&self, c: char, pos: usize, len: usize) -> bool {
+        self.applies_in(c, pos, len, Place::WHOLE)
+    }
+
+    // `applies` for char `pos` of a `len` char part of a concatenation
+    fn applies_in(&self, c: char, pos: usize, len: usize, place: Place) -> bool {
+        c == self.from()
+            && match self.2 {
+                Anchor::Anywhere => true,
+                Anchor::Start => place.first && pos == 0,
+                Anchor::End => place.last && pos + 1 == len,
+            }
+    }
+}
+
+/// Unconditional 1:1 character translation applied to base words, like `tr`
+#[derive(Debug, Clone)]
+pub struct Transliteration(pub Vec<(char, char)>);
+
+impl Transliteration {
+    pub fn apply(&self, word: &str) -> String {
+        word.chars()
+            .map(|c| {
+                self.0
+                    .iter()
+                    .find(|(from, _)| *from == c)
+                    .map_or(c, |(_, to)| *to)
+            })
+            .collect()
+    }
+}
+
+/// Inclusive range of numbers zero-padded to `width` digits (`--numeric-range`)
+#[derive(Debug, Clone, Copy)]
+pub struct NumericRange {
+    pub start: u64,
+    pub end: u64,
+    pub width: usize,
+}
+
+impl NumericRange {
+    pub fn numbers(&self) -> impl Iterator<Item = String> {
+        let width = self.width;
+        (self.start..=self.end).map(move |n| format!("{:0width$}", n))
+    }
+
+    /// Write every number within the length range as-is, without casing or leet
+    pub fn generate(&self, writer: &mut Emitter, opts: &Options) -> io::Result<()> {
+        for number in self.numbers() {
+            if writer.is_done() {
+                break;
+            }
+            if (opts.min_length..=opts.max_length).contains(&number.len()) {
+                writer.emit(&number)?;
+            }
+        }
+        writer.flush()
+    }
+
+    // counted per digit count rather than by formatting every number
+    pub fn count(&self, opts: &Options) -> u128 {
+        let mut count = 0;
+        for digits in 1..=20u32 {
+            let low = if digits == 1 {
+                0
+            } else {
+                10u64.pow(digits - 1)
+            };
+            let high = 10u64.checked_pow(digits).map_or(u64::MAX, |p| p - 1);
+            let len = self.width.max(digits as usize);
+            let (from, to) = (self.start.max(low), self.end.min(high));
+            if from <= to && (opts.min_length..=opts.max_length).contains(&len) {
+                count += u128::from(to - from) + 1;
+            }
+        }
+        count
+    }
+}
+
+/// How a word handed to the casing and leet stages was built
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    Word,
+    Doubled,
+    Concat,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Source<'s> {
+    pub kind: SourceKind,
+    /// Dictionary words and terms it was made from
+    pub parts: &'s [&'s str],
+}
+
+impl Source<'_> {
+    // mutation kinds already applied, for `max_mutations`
+    fn mutations(&self) -> usize {
+        match self.kind {
+            SourceKind::Word => 0,
+            SourceKind::Doubled | SourceKind::Concat => 1,
+        }
+    }
+}
+
+/// Settings shared by every stage of generation
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub transforms: Vec<ReplacePair>,
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Also emit base words with one character doubled (i.e. "cat" => "ccat", "caat", "catt")
+    pub double_chars: bool,
+    /// Longest word or term allowed as one part of a concatenation
+    pub max_part_length: Option<usize>,
+    /// Longest concatenation, defaults to `max_length`
+    pub max_concat_length: Option<usize>,
+    /// Only join two parts, never chain further
+    pub no_recurse: bool,
+    /// Never use the same word twice in one concatenation
+    pub no_repeat_terms: bool,
+    /// Only join terms after dictionary words, never a term then a dictionary word
+    pub dictionary_first: bool,
+    /// Instead of every concatenation, only join each word with itself ("catcat")
+    pub doubled: bool,
+    pub join_style: JoinStyle,
+    /// Only emit words containing all of these
+    pub must_contain: Vec<String>,
+    /// Most mutation kinds (casing, leet, concatenation, doubling) that may stack on one word
+    pub max_mutations: Option<usize>,
+    /// Try only this targeted casing instead of every casing
+    pub capitalize_after: Option<CapitalizeAfter>,
+    /// Only emit variants where at least one replacement applied, for the passes after
+    /// the first with `--replacements-set`
+    pub replaced_only: bool,
+    /// Most replacements applied to one word
+    pub max_leet_subs: Option<usize>,
+    /// Permutate words in the casing they were given instead of lowercasing them first
+    pub preserve_base_case: bool,
+    /// `preserve_base_case` for terms on their own only, concatenations are unaffected
+    pub terms_preserve_case: bool,
+    /// Print every base word to stdout as it's permutated
+    pub echo: bool,
+}
+
+/// Targeted casing: capitalize the first letter of the word and the first letter after
+/// each boundary, instead of trying every combination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CapitalizeAfter {
+    /// admin_login => Admin_Login
+    Separator,
+    /// admin1login => Admin1Login
+    Digit,
+}
+
+impl CapitalizeAfter {
+    // the lowercase `word`, plus the capitalized form when it differs, each with the
+    // char positions of `word` it uppercased
+    fn casings(&self, word: &str) -> Vec<(String, Vec<usize>)> {
+        let (cased, flipped, _) = self.capitalize(word, true);
+        if flipped.is_empty() {
+            vec![(cased, flipped)]
+        } else {
+            vec![(word.to_string(), Vec::new()), (cased, flipped)]
+        }
+    }
+
+    // `word` with the first letter after each boundary uppercased, starting on a boundary
+    // if `boundary`, with the positions uppercased and whether it ends on a boundary
+    fn capitalize(&self, word: &str, mut boundary: bool) -> (String, Vec<usize>, bool) {
+        let mut cased = String::with_capacity(word.len());
+        let mut flipped = Vec::new();
+        for (i, c) in word.chars().enumerate() {
+            if c.is_alphabetic() {
+                if boundary {
+                    if c.to_uppercase().ne(std::iter::once(c)) {
+                        flipped.push(i);
+                    }
+                    cased.extend(c.to_uppercase());
+                } else {
+                    cased.push(c);
+                }
+                boundary = false;
+            } else {
+                cased.push(c);
+                boundary |= match self {
+                    CapitalizeAfter::Separator => !c.is_alphanumeric(),
+                    CapitalizeAfter::Digit => c.is_numeric(),
+                };
+            }
+        }
+        (cased, flipped, boundary)
+    }
+}
+
+/// How the parts of a concatenation are joined.
+///
+/// Casing permutations already cover every casing of a joined word, so `camel` and
+/// `title` only change the form that is permutated; `snake` adds new words.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum JoinStyle {
+    /// adminpassword
+    #[default]
+    Plain,
+    /// adminPassword
+    Camel,
+    /// AdminPassword
+    Title,
+    /// admin_password
+    Snake,
+}
+
+impl Options {
+    // an empty part would only repeat the word it's joined to, forever when recursing
+    fn is_part(&self, word: &str) -> bool {
+        !word.is_empty() && word.len() <= self.max_part_length.unwrap_or(self.max_length)
+    }
+
+    // mutation kinds still allowed on a word that already has `used` applied
+    fn mutations_left(&self, used: usize) -> usize {
+        self.max_mutations
+            .map_or(usize::MAX, |max| max.saturating_sub(used))
+    }
+
+    // the form casing masks are applied to
+    fn base(&self, word: &str) -> String {
+        if self.preserve_base_case {
+            word.to_string()
+        } else {
+            word.to_lowercase()
+        }
+    }
+
+    // options for a term on its own
+    fn for_terms(&self) -> Options {
+        Options {
+            preserve_base_case: self.preserve_base_case || self.terms_preserve_case,
+            ..self.clone()
+        }
+    }
+
+    // options for a concatenation, a camel or title join is cased from the form the style
+    // gave it. With no casing left it's lowercased and kept as given, as a concat always was
+    fn for_concats(&self) -> Options {
+        let styled = matches!(self.join_style, JoinStyle::Camel | JoinStyle::Title);
+        Options {
+            preserve_base_case: self.preserve_base_case || (styled && self.mutations_left(1) > 0),
+            ..self.clone()
+        }
+    }
+
+    fn can_concat(&self) -> bool {
+        self.mutations_left(0) > 0
+    }
+
+    fn concat_budget(&self) -> usize {
+        self.max_concat_length.unwrap_or(self.max_length)
+    }
+
+    fn separator(&self) -> &'static str {
+        match self.join_style {
+            JoinStyle::Snake => "_",
+            _ => "",
+        }
+    }
+
+    // a part as it appears in a concatenation, capitalized by the join style
+    fn as_joined(&self, part: &str, place: Place) -> String {
+        match self.join_style {
+            JoinStyle::Title if !(place.first && place.last) => capitalize(part),
+            JoinStyle::Camel if !place.first => capitalize(part),
+            _ => part.to_string(),
+        }
+    }
+
+    // whether targeted casing would uppercase the next letter after `part`
+    fn boundary_after(&self, part: &str, boundary: bool) -> bool {
+        self.capitalize_after
+            .is_some_and(|mode| mode.capitalize(part, boundary).2)
+    }
+
+    fn join(&self, head: &str, part: &str) -> String {
+        match self.join_style {
+            JoinStyle::Plain | JoinStyle::Snake => format!("{}{}{}", head, self.separator(), part),
+            JoinStyle::Camel => format!("{}{}", head, capitalize(part)),
+            JoinStyle::Title => format!("{}{}", capitalize(head), capitalize(part)),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
 }
 
 pub fn generate_wordlist(
-    fout: &File,
-    dictionary: Vec<String>,
-    terms: Vec<String>,
-    transforms: &Vec<ReplacePair>,
-    min_length: usize,
-    max_length: usize,
+    writer: &mut Emitter,
+    dictionary: &[String],
+    terms: &[String],
+    opts: &Options,
 ) -> io::Result<()> {
-    let mut writer = BufWriter::new(fout);
-
-    for word1 in &dictionary {
+    let concat_opts = opts.for_concats();
+    for word1 in dictionary {
+        if writer.is_done() {
+            break;
+        }
         // word
-        if word1.len() >= min_length {
-            generate_permutations(&mut writer, word1, transforms)?;
+        let start = writer.start();
+        generate_base(writer, word1, opts)?;
+        writer.record("single words", start);
+        if !opts.can_concat() || !opts.is_part(word1) {
+            continue;
+        }
+        let start = writer.start();
+        if opts.doubled {
+            generate_doubled(writer, word1, &concat_opts)?;
+            writer.record("concatenation", start);
+            continue;
         }
         // word + word
-        generate_concats(
-            &mut writer,
-            word1,
-            &dictionary,
-            transforms,
-            min_length,
-            max_length,
-        )?;
+        generate_concats(writer, word1, &[word1], dictionary, &concat_opts)?;
         // word + term
-        generate_concats(
-            &mut writer,
-            word1,
-            &terms,
-            transforms,
-            min_length,
-            max_length,
-        )?;
+        generate_concats(writer, word1, &[word1], terms, &concat_opts)?;
+        writer.record("concatenation", start);
     }
 
-    for term1 in &terms {
+    let term_opts = opts.for_terms();
+    for term1 in terms {
+        if writer.is_done() {
+            break;
+        }
         // term
-        if term1.len() >= min_length {
-            generate_permutations(&mut writer, term1, transforms)?;
+        let start = writer.start();
+        generate_base(writer, term1, &term_opts)?;
+        writer.record("single words", start);
+        if !opts.can_concat() || !opts.is_part(term1) {
+            continue;
+        }
+        let start = writer.start();
+        if opts.doubled {
+            generate_doubled(writer, term1, &concat_opts)?;
+            writer.record("concatenation", start);
+            continue;
         }
         // term + term
-        generate_concats(
-            &mut writer,
-            term1,
-            &terms,
-            transforms,
-            min_length,
-            max_length,
-        )?;
-        // term + word
-        generate_concats(
-            &mut writer,
-            term1,
-            &dictionary,
-            transforms,
-            min_length,
-            max_length,
-        )?;
+        generate_concats(writer, term1, &[term1], terms, &concat_opts)?;
+        // term + word, word + term was covered above
+        if !opts.dictionary_first {
+            generate_concats(writer, term1, &[term1], dictionary, &concat_opts)?;
+        }
+        writer.record("concatenation", start);
     }
 
     writer.flush()?;
     Ok(())
 }
 
-fn generate_concats(
-    writer: &mut BufWriter<&File>,
-    term: &String,
-    terms: &Vec<String>,
-    transforms: &Vec<ReplacePair>,
-    min_length: usize,
-    max_length: usize,
+// a dictionary word or term on its own, plus its mutations
+fn generate_base(writer: &mut Emitter, word: &String, opts: &Options) -> io::Result<()> {
+    let parts = [word.as_str()];
+    if word.len() >= opts.min_length {
+        let source = Source {
+            kind: SourceKind::Word,
+            parts: &parts,
+        };
+        generate_permutations(writer, word, source, opts)?;
+    }
+    if opts.double_chars && opts.mutations_left(0) > 0 {
+        let source = Source {
+            kind: SourceKind::Doubled,
+            parts: &parts,
+        };
+        for doubled in double_chars(word) {
+            if doubled.len() >= opts.min_length && doubled.len() <= opts.max_length {
+                generate_permutations(writer, &doubled, source, opts)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// "cat" => "ccat", "caat", "catt"; runs of the same char only double once
+fn double_chars(word: &str) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut doubled = Vec::new();
+    for i in 0..chars.len() {
+        if chars.get(i + 1) == Some(&chars[i]) {
+            continue;
+        }
+        let mut variant: String = chars[..=i].iter().collect();
+        variant.push(chars[i]);
+        variant.extend(&chars[i + 1..]);
+        doubled.push(variant);
+    }
+    doubled
+}
+
+fn generate_doubled(writer: &mut Emitter, word: &str, opts: &Options) -> io::Result<()> {
+    let doubled = opts.join(word, word);
+    if doubled.len() >= opts.min_length && doubled.len() <= opts.concat_budget() {
+        let source = Source {
+            kind: SourceKind::Concat,
+            parts: &[word, word],
+        };
+        generate_permutations(writer, &doubled, source, opts)?;
+    }
+    Ok(())
+}
+
+// `parts` are the words already joined into `term`
+fn generate_concats<'s>(
+    writer: &mut Emitter,
+    term: &str,
+    parts: &[&'s str],
+    terms: &'s [String],
+    opts: &Options,
 ) -> io::Result<()> {
-    if terms.len() <= 0 {
+    if terms.is_empty() {
         return Ok(());
     }
 
     for term1 in terms {
-        if term1.len() > max_length {
+        if writer.is_done() {
+            break;
+        }
+        if !opts.is_part(term1) || (opts.no_repeat_terms && parts.contains(&term1.as_str())) {
             continue;
         }
 
         // term + term
-        let term_term = format!("{}{}", term, term1);
-        if term_term.len() > max_length {
+        let term_term = opts.join(term, term1);
+        if term_term.len() > opts.concat_budget() {
             continue;
         }
 
-        if term_term.len() >= min_length {
-            generate_permutations(writer, &term_term, transforms)?;
+        let mut term_parts = parts.to_vec();
+        term_parts.push(term1);
+        // a join still under min_length isn't written but is still extended below, so
+        // every chain within [min_length, budget] is reached however short its parts
+        if term_term.len() >= opts.min_length {
+            let source = Source {
+                kind: SourceKind::Concat,
+                parts: &term_parts,
+            };
+            generate_permutations(writer, &term_term, source, opts)?;
         }
         // recurse
-        generate_concats(
-            writer, &term_term, terms, transforms, min_length, max_length,
-        )?;
+        if !opts.no_recurse {
+            generate_concats(writer, &term_term, &term_parts, terms, opts)?;
+        }
     }
 
-    writer.flush()?;
     Ok(())
 }
 
 // adds capitalization and transforms
+// casing is Unicode aware, one mask bit per char. Uppercasing can change the byte length
+// (e.g. "ß" => "SS"), so a variant may fall outside the range the base word was checked against.
+// a set bit flips the char's case, which for the default lowercased base is uppercasing; with
+// `preserve_base_case` mask 0 is the word as given ("iPhone") and the others toggle from it.
 fn generate_permutations(
-    writer: &mut BufWriter<&File>,
+    writer: &mut Emitter,
     w: &String,
-    replacements: &Vec<ReplacePair>,
+    source: Source,
+    opts: &Options,
 ) -> io::Result<()> {
-    println!("{}", w);
+    if opts.echo {
+        println!("{}", w);
+    }
 
-    let word = w.to_lowercase();
-    for i in 0..(1 << word.len()) {
+    let word = opts.base(w);
+    if !could_contain(&word.to_lowercase(), opts) {
+        return Ok(());
+    }
+    let left = opts.mutations_left(source.mutations());
+    if let Some(mode) = opts.capitalize_after.filter(|_| left > 0) {
+        // the word as given isn't a casing mutation, it's kept when the targeted casing misses it
+        let mut casings = mode.casings(&word);
+        if !casings.iter().any(|(cased, _)| cased == w) {
+            casings.push((w.clone(), flipped_from_base(w, opts)));
+        }
+        for (i, (cased, flipped)) in casings.iter().enumerate() {
+            let provenance = Provenance {
+                source,
+                base: &word,
+                cased,
+                flipped,
+                leet: &[],
+            };
+            let casing = usize::from(i != 0 && cased != w);
+            add_transformations(writer, &provenance, opts, left > casing)?;
+        }
+        return Ok(());
+    }
+    // one mask bit per char that has an uppercase, digits and symbols would only
+    // repeat the same variants
+    let cased: Vec<bool> = word.chars().map(has_case).collect();
+    let letters = cased.iter().filter(|&&c| c).count() as u32;
+    let masks: u128 = match left {
+        0 => 1,
+        _ => 1u128.checked_shl(letters).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("`{}` has too many letters to try every casing", w),
+            )
+        })?,
+    };
+    for i in 0..masks {
+        if writer.is_done() {
+            break;
+        }
         let mut combination = String::new();
-        for (j, c) in word.chars().enumerate() {
-            if (i >> j) & 1 == 1 {
-                combination.push(c.to_ascii_uppercase());
+        let mut flipped = Vec::new();
+        let mut bit = 0;
+        for (pos, (c, &cased)) in word.chars().zip(&cased).enumerate() {
+            let flip = cased && (i >> bit) & 1 == 1;
+            if flip {
+                flipped.push(pos);
+            }
+            if flip && c.is_uppercase() {
+                combination.extend(c.to_lowercase());
+            } else if flip {
+                combination.extend(c.to_uppercase());
             } else {
                 combination.push(c);
             }
+            bit += usize::from(cased);
         }
         // for each caps, transform
-        add_transformations(writer, combination.as_str(), replacements)?;
+        let casing = usize::from(i != 0);
+        let provenance = Provenance {
+            source,
+            base: &word,
+            cased: &combination,
+            flipped: &flipped,
+            leet: &[],
+        };
+        add_transformations(writer, &provenance, opts, left > casing)?;
+    }
+    // with no casing the only mask is lowercase, the word as given is kept as well
+    if left == 0 && *w != word && !writer.is_done() {
+        let flipped = flipped_from_base(w, opts);
+        let provenance = Provenance {
+            source,
+            base: &word,
+            cased: w,
+            flipped: &flipped,
+            leet: &[],
+        };
+        add_transformations(writer, &provenance, opts, false)?;
     }
 
     Ok(())
 }
 
+// positions of `opts.base(w)` that `w` has in another case, counted along the base so a
+// char lowercasing to several ("İ") doesn't shift the ones after it
+fn flipped_from_base(w: &str, opts: &Options) -> Vec<usize> {
+    if opts.preserve_base_case {
+        return Vec::new();
+    }
+    let mut flipped = Vec::new();
+    let mut pos = 0;
+    for c in w.chars() {
+        if c.to_lowercase().ne(std::iter::once(c)) {
+            flipped.push(pos);
+        }
+        pos += c.to_lowercase().count();
+    }
+    flipped
+}
+
+fn has_case(c: char) -> bool {
+    c.to_uppercase().ne(std::iter::once(c)) || c.to_lowercase().ne(std::iter::once(c))
+}
+
+// whether any casing/leet variant of the lowercase `word` could contain every `must_contain`
+// substring, so words that never can are skipped before the 2^n casing loop
+fn could_contain(word: &str, opts: &Options) -> bool {
+    if opts.must_contain.is_empty() {
+        return true;
+    }
+    // chars each position can become, casing that changes the char count isn't pruned
+    let mut options: Vec<Vec<char>> = Vec::new();
+    for c in word.chars() {
+        let mut upper = c.to_uppercase();
+        let (Some(u), None) = (upper.next(), upper.next()) else {
+            return true;
+        };
+        let mut chars = vec![c, u];
+        for r in &opts.transforms {
+            if r.from() == c || r.from() == u {
+                chars.push(r.to());
+            }
+        }
+        options.push(chars);
+    }
+    opts.must_contain.iter().all(|needle| {
+        let needle: Vec<char> = needle.chars().collect();
+        needle.len() <= options.len()
+            && (0..=options.len() - needle.len()).any(|start| {
+                needle
+                    .iter()
+                    .zip(&options[start..])
+                    .all(|(c, chars)| chars.contains(c))
+            })
+    })
+}
+
 // "leet" transforms
 fn add_transformations(
-    writer: &mut BufWriter<&File>,
-    word: &str,
-    replacements: &Vec<ReplacePair>,
+    writer: &mut Emitter,
+    provenance: &Provenance,
+    opts: &Options,
+    leet: bool,
 ) -> io::Result<()> {
-    let mut current = vec![String::new()];
-    for c in word.chars() {
-        let mut new_combinations = Vec::new();
-        for combo in current.iter() {
-            new_combinations.push(combo.clone() + &c.to_string());
-            for r in replacements {
-                if c == r.from() {
-                    new_combinations.push(combo.clone() + &r.to().to_string());
+    let word = provenance.cased;
+    let len = word.chars().count();
+    // what each char can become, itself first
+    let choices: Vec<Vec<char>> = word
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let subs = opts
+                .transforms
+                .iter()
+                .filter(|r| leet && r.applies(c, i, len))
+                .map(|r| r.to());
+            std::iter::once(c).chain(subs).collect()
+        })
+        .collect();
+    let mut combo = String::with_capacity(word.len());
+    write_variants(
+        writer,
+        provenance,
+        opts,
+        &choices,
+        0,
+        &mut combo,
+        &mut Vec::new(),
+    )
+}
+
+// depth first over the choices left, writing each variant as soon as it's complete so
+// only one is held at a time. the word with no replacements comes first, later chars vary
+// fastest. `pos` is the char position of `choices[0]`, `subs` the replacements
+// already in `combo`
+fn write_variants(
+    writer: &mut Emitter,
+    provenance: &Provenance,
+    opts: &Options,
+    choices: &[Vec<char>],
+    pos: usize,
+    combo: &mut String,
+    subs: &mut Vec<(usize, char, char)>,
+) -> io::Result<()> {
+    let Some((first, rest)) = choices.split_first() else {
+        let keep = !(opts.replaced_only && subs.is_empty())
+            && opts
+                .must_contain
+                .iter()
+                .all(|needle| combo.contains(needle.as_str()));
+        if keep {
+            let provenance = Provenance {
+                leet: subs,
+                ..*provenance
+            };
+            writer.emit_from(combo, Some(&provenance))?;
+        }
+        return Ok(());
+    };
+    let max_subs = opts.max_leet_subs.unwrap_or(usize::MAX);
+    for (i, &c) in first.iter().enumerate() {
+        // at the limit only the unchanged char can follow
+        if (i > 0 && subs.len() >= max_subs) || writer.is_done() {
+            break;
+        }
+        if i > 0 {
+            subs.push((pos, first[0], c));
+        }
+        combo.push(c);
+        write_variants(writer, provenance, opts, rest, pos + 1, combo, subs)?;
+        combo.pop();
+        if i > 0 {
+            subs.pop();
+        }
+    }
+    Ok(())
+}
+
+/// Number of words `generate_wordlist` would emit for these inputs, without generating them.
+///
+/// Casing and leet branch independently per character, so the count for a word is a
+/// product over its chars, and the count for a concatenation is the product of its parts.
+/// Filters on the finished words, like `must_contain`, are not accounted for.
+pub fn estimate_permutation_count(dictionary: &[String], terms: &[String], opts: &Options) -> u128 {
+    if !opts.replaced_only {
+        return estimate_all(dictionary, terms, opts);
+    }
+    // every variant minus the ones left unchanged, which are what no rules at all gives
+    let unchanged = Options {
+        transforms: Vec::new(),
+        replaced_only: false,
+        ..opts.clone()
+    };
+    estimate_all(dictionary, terms, opts)
+        .saturating_sub(estimate_all(dictionary, terms, &unchanged))
+}
+
+fn estimate_all(dictionary: &[String], terms: &[String], opts: &Options) -> u128 {
+    let mut total: u128 = 0;
+    for word in dictionary {
+        total = total.saturating_add(estimate_base(word, opts));
+    }
+    let term_opts = opts.for_terms();
+    for term in terms {
+        total = total.saturating_add(estimate_base(term, &term_opts));
+    }
+    if !opts.can_concat() {
+        return total;
+    }
+    let opts = &opts.for_concats();
+    if opts.doubled {
+        let doubled = dictionary
+            .iter()
+            .chain(terms)
+            .filter(|word| opts.is_part(word));
+        for word in doubled.map(|word| opts.join(word, word)) {
+            if word.len() >= opts.min_length && word.len() <= opts.concat_budget() {
+                total = total.saturating_add(estimate_word(&word, opts, 1));
+            }
+        }
+        return total;
+    }
+
+    // with one mutation left a concatenation gets either leet or casing, which is
+    // leet-only + casing-only - the plain word counted by both; each is still a product
+    let targeted = opts.capitalize_after.is_some() && opts.mutations_left(1) > 0;
+    let components: &[(Weight, bool)] = match opts.mutations_left(1) {
+        0 => &[(Weight::One, true)],
+        1 if targeted => TARGETED_ONE_LEFT,
+        _ if targeted => TARGETED,
+        1 => &[
+            (Weight::Leet, true),
+            (Weight::Casing, true),
+            (Weight::One, false),
+        ],
+        _ => &[(Weight::Full, true)],
+    };
+    let given: &[(Weight, bool)] = if targeted { TARGETED_GIVEN } else { &[] };
+    let all = |_: &str, _: bool| true;
+    let (mut added, mut removed) = (0u128, 0u128);
+    for &(kind, add) in components.iter().chain(given) {
+        let count = estimate_all_concats(dictionary, terms, opts, kind, all);
+        if add {
+            added = added.saturating_add(count);
+        } else {
+            removed = removed.saturating_add(count);
+        }
+    }
+    if opts.mutations_left(1) == 0 && !opts.preserve_base_case {
+        // the joined word as given is kept alongside its lowercase form, unless they match
+        let lowercase = |part: &str, first: bool| {
+            let part = match (opts.join_style, first) {
+                (JoinStyle::Title, _) | (JoinStyle::Camel, false) => capitalize(part),
+                _ => part.to_string(),
+            };
+            part == part.to_lowercase()
+        };
+        let unchanged = estimate_all_concats(dictionary, terms, opts, Weight::One, lowercase);
+        let joined = estimate_all_concats(dictionary, terms, opts, Weight::One, all);
+        added = added.saturating_add(joined.saturating_sub(unchanged));
+    }
+    total.saturating_add(added.saturating_sub(removed))
+}
+
+// with targeted casing a concatenation is written lowercase with leet, capitalized with leet
+// when that differs, and as given with leet when it's neither. Which forms match depends on
+// every part, so the conditions are counted by inclusion-exclusion
+const TARGETED: &[(Weight, bool)] = &[
+    (Weight::Targeted(Some(Form::Lower), &[]), true),
+    (Weight::Targeted(Some(Form::Capitalized), &[]), true),
+    (
+        Weight::Targeted(Some(Form::Capitalized), &[(Form::Capitalized, Form::Lower)]),
+        false,
+    ),
+];
+
+// with one mutation left the capitalized form only gets leet when it's the word as given
+const TARGETED_ONE_LEFT: &[(Weight, bool)] = &[
+    (Weight::Targeted(Some(Form::Lower), &[]), true),
+    (
+        Weight::Targeted(Some(Form::Capitalized), &[(Form::Capitalized, Form::Given)]),
+        true,
+    ),
+    (
+        Weight::Targeted(
+            Some(Form::Capitalized),
+            &[
+                (Form::Capitalized, Form::Given),
+                (Form::Capitalized, Form::Lower),
+            ],
+        ),
+        false,
+    ),
+    (Weight::Targeted(None, &[]), true),
+    (
+        Weight::Targeted(None, &[(Form::Capitalized, Form::Given)]),
+        false,
+    ),
+    (
+        Weight::Targeted(None, &[(Form::Capitalized, Form::Lower)]),
+        false,
+    ),
+    (
+        Weight::Targeted(
+            None,
+            &[
+                (Form::Capitalized, Form::Given),
+                (Form::Capitalized, Form::Lower),
+            ],
+        ),
+        true,
+    ),
+];
+
+// the word as given, when it's neither of the other forms
+const TARGETED_GIVEN: &[(Weight, bool)] = &[
+    (Weight::Targeted(Some(Form::Given), &[]), true),
+    (
+        Weight::Targeted(Some(Form::Given), &[(Form::Given, Form::Lower)]),
+        false,
+    ),
+    (
+        Weight::Targeted(Some(Form::Given), &[(Form::Given, Form::Capitalized)]),
+        false,
+    ),
+    (
+        Weight::Targeted(
+            Some(Form::Given),
+            &[(Form::Given, Form::Lower), (Form::Given, Form::Capitalized)],
+        ),
+        true,
+    ),
+];
+
+// only counts concatenations whose parts all pass `keep(part, is_first)`
+fn estimate_all_concats(
+    dictionary: &[String],
+    terms: &[String],
+    opts: &Options,
+    kind: Weight,
+    keep: impl Fn(&str, bool) -> bool,
+) -> u128 {
+    let kept = |list: &[String]| -> Vec<String> {
+        list.iter().filter(|p| keep(p, false)).cloned().collect()
+    };
+    let (kept_dictionary, kept_terms) = (kept(dictionary), kept(terms));
+    let after_terms: &[String] = if opts.dictionary_first {
+        &[]
+    } else {
+        &kept_dictionary
+    };
+    let mut total: u128 = 0;
+    for (firsts, others) in [
+        (dictionary, [&kept_dictionary[..], &kept_terms[..]]),
+        (terms, [&kept_terms[..], after_terms]),
+    ] {
+        let mut memos = [HashMap::new(), HashMap::new()];
+        let firsts = firsts.iter().filter(|first| keep(first, true));
+        for first in firsts.filter(|first| opts.is_part(first)) {
+            let place = Place {
+                last: false,
+                ..Place::WHOLE
+            };
+            let weight = kind.by_subs(first, opts, place);
+            let boundary = opts.boundary_after(first, place.boundary);
+            for (parts, memo) in others.iter().zip(memos.iter_mut()) {
+                let mut used = vec![first.as_str()];
+                let (len, parts) = (first.len(), &parts[..]);
+                let tails = estimate_concats(len, boundary, &mut used, parts, opts, kind, memo);
+                total = total.saturating_add(sum(&times(&weight, &tails, opts)));
+            }
+        }
+    }
+    total
+}
+
+fn estimate_base(word: &str, opts: &Options) -> u128 {
+    let mut count = 0;
+    if word.len() >= opts.min_length {
+        count = estimate_word(word, opts, 0);
+    }
+    if opts.double_chars && opts.mutations_left(0) > 0 {
+        for doubled in double_chars(word) {
+            if doubled.len() >= opts.min_length && doubled.len() <= opts.max_length {
+                count = count.saturating_add(estimate_word(&doubled, opts, 1));
+            }
+        }
+    }
+    count
+}
+
+// weighted variants of everything that can follow a prefix of length `len` ending on a
+// `capitalize_after` boundary or not, memoized by both. with `no_repeat_terms` that also
+// depends on the `used` parts, so nothing is memoized
+fn estimate_concats<'s>(
+    len: usize,
+    boundary: bool,
+    used: &mut Vec<&'s str>,
+    parts: &'s [String],
+    opts: &Options,
+    kind: Weight,
+    memo: &mut HashMap<(usize, bool), Vec<u128>>,
+) -> Vec<u128> {
+    let memoize = !opts.no_repeat_terms;
+    if let Some(count) = memo.get(&(len, boundary)).filter(|_| memoize) {
+        return count.clone();
+    }
+    let separator = opts.separator();
+    let inside = Place {
+        first: false,
+        last: false,
+        boundary,
+    };
+    let separated = kind.by_subs(separator, opts, inside);
+    let after_separator = opts.boundary_after(separator, boundary);
+    let mut count = vec![0];
+    for part in parts {
+        let joined = len + separator.len() + part.len();
+        let repeated = opts.no_repeat_terms && used.contains(&part.as_str());
+        if !opts.is_part(part) || joined > opts.concat_budget() || repeated {
+            continue;
+        }
+        // anchored rules see the part differently when it ends the word
+        let last = Place {
+            first: false,
+            last: true,
+            boundary: after_separator,
+        };
+        let ends = [u128::from(joined >= opts.min_length)];
+        let mut tails = times(&kind.by_subs(part, opts, last), &ends, opts);
+        if !opts.no_recurse {
+            used.push(part);
+            let boundary = opts.boundary_after(part, after_separator);
+            let more = estimate_concats(joined, boundary, used, parts, opts, kind, memo);
+            used.pop();
+            let inside = Place {
+                last: false,
+                ..last
+            };
+            tails = plus(
+                &tails,
+                &times(&kind.by_subs(part, opts, inside), &more, opts),
+            );
+        }
+        count = plus(&count, &times(&separated, &tails, opts));
+    }
+    if memoize {
+        memo.insert((len, boundary), count.clone());
+    }
+    count
+}
+
+// counts indexed by the number of replacements in the variants, so the `max_leet_subs`
+// cap holds across a whole concatenation. Without a cap they're kept as a single total
+fn times(a: &[u128], b: &[u128], opts: &Options) -> Vec<u128> {
+    let Some(max_subs) = opts.max_leet_subs else {
+        return vec![sum(a).saturating_mul(sum(b))];
+    };
+    let mut product = vec![0u128; (a.len() + b.len() - 1).min(max_subs + 1)];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate().take(product.len().saturating_sub(i)) {
+            product[i + j] = product[i + j].saturating_add(x.saturating_mul(y));
+        }
+    }
+    product
+}
+
+fn plus(a: &[u128], b: &[u128]) -> Vec<u128> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut total = long.to_vec();
+    for (n, m) in total.iter_mut().zip(short) {
+        *n = n.saturating_add(*m);
+    }
+    total
+}
+
+fn sum(counts: &[u128]) -> u128 {
+    counts.iter().fold(0, |acc, &n| acc.saturating_add(n))
+}
+
+// where a part sits in its concatenation, for anchored rules and targeted casing
+#[derive(Clone, Copy)]
+struct Place {
+    first: bool,
+    last: bool,
+    /// `capitalize_after` would uppercase the first letter of the part
+    boundary: bool,
+}
+
+impl Place {
+    // a word on its own
+    const WHOLE: Place = Place {
+        first: true,
+        last: true,
+        boundary: true,
+    };
+}
+
+// forms of a word written with targeted casing
+#[derive(Clone, Copy)]
+enum Form {
+    Lower,
+    Capitalized,
+    Given,
+}
+
+// per-part factor of a count that multiplies across the parts of a concatenation
+#[derive(Clone, Copy)]
+enum Weight {
+    /// every casing with every leet
+    Full,
+    /// leet on the lowercase word only
+    Leet,
+    /// every casing without leet
+    Casing,
+    One,
+    /// leet on one `capitalize_after` form, or none, counted only where each pair of
+    /// forms is the same
+    Targeted(Option<Form>, &'static [(Form, Form)]),
+}
+
+impl Weight {
+    fn of(&self, word: &str, opts: &Options) -> u128 {
+        sum(&self.by_subs(word, opts, Place::WHOLE))
+    }
+
+    // variants of `word` by the number of replacements in them, see `times`
+    fn by_subs(&self, word: &str, opts: &Options, place: Place) -> Vec<u128> {
+        // leet alone applies to the base as is, the other weights cover both casings
+        let word = match self {
+            Weight::Leet => {
+                return leet_by_subs(&opts.base(&opts.as_joined(word, place)), opts, place)
+            }
+            Weight::Targeted(leet, equal) => {
+                let given = opts.as_joined(word, place);
+                let lower = opts.base(&given);
+                let cased = match opts.capitalize_after {
+                    Some(mode) => mode.capitalize(&lower, place.boundary).0,
+                    None => lower.clone(),
+                };
+                let form = |form: Form| match form {
+                    Form::Lower => &lower,
+                    Form::Capitalized => &cased,
+                    Form::Given => &given,
+                };
+                if equal.iter().any(|&(a, b)| form(a) != form(b)) {
+                    return vec![0];
                 }
+                return match leet {
+                    Some(leet) => leet_by_subs(form(*leet), opts, place),
+                    None => vec![1],
+                };
+            }
+            _ => word.to_lowercase(),
+        };
+        let len = word.chars().count();
+        let branches = |c: char, i: usize| {
+            1 + opts
+                .transforms
+                .iter()
+                .filter(|r| r.applies_in(c, i, len, place))
+                .count() as u128
+        };
+        // (variants of the char left as is, variants with a replacement)
+        let per_char = |(i, c): (usize, char)| match self {
+            Weight::Full if !has_case(c) => (1, branches(c, i) - 1),
+            Weight::Full => {
+                let upper = c.to_uppercase().map(|u| branches(u, i)).product::<u128>();
+                (2, branches(c, i) + upper - 2)
             }
+            Weight::Casing => (1 + u128::from(has_case(c)), 0),
+            _ => (1, 0),
+        };
+        word.chars()
+            .enumerate()
+            .map(per_char)
+            .fold(vec![1], |acc, (same, subs)| {
+                times(&acc, &[same, subs], opts)
+            })
+    }
+}
+
+// casing * leet variants of a single word as generate_permutations would emit them,
+// after `used` mutation kinds were already applied
+fn estimate_word(word: &str, opts: &Options, used: usize) -> u128 {
+    let left = opts.mutations_left(used);
+    let base = opts.base(word);
+    if let Some(mode) = opts.capitalize_after.filter(|_| left > 0) {
+        let mut casings: Vec<String> = mode.casings(&base).into_iter().map(|(c, _)| c).collect();
+        if !casings.iter().any(|cased| cased == word) {
+            casings.push(word.to_string());
         }
-        current = new_combinations;
+        let counts = casings.iter().enumerate().map(|(i, cased)| {
+            let casing = usize::from(i != 0 && cased != word);
+            match left > casing {
+                true => leet_count(cased, opts),
+                false => 1,
+            }
+        });
+        return counts.fold(0, u128::saturating_add);
+    }
+    match left {
+        0 => 1 + u128::from(base != word),
+        1 => (Weight::Leet.of(word, opts) + Weight::Casing.of(word, opts)).saturating_sub(1),
+        _ => Weight::Full.of(word, opts),
     }
+}
 
-    for combo in current {
-        writer.write_all(combo.as_bytes())?;
-        writer.write_all(b"\n")?;
+// leet variants of an already cased word
+fn leet_count(word: &str, opts: &Options) -> u128 {
+    sum(&leet_by_subs(word, opts, Place::WHOLE))
+}
+
+// leet variants of an already cased word or part by the number of replacements, see `times`
+fn leet_by_subs(word: &str, opts: &Options, place: Place) -> Vec<u128> {
+    let len = word.chars().count();
+    let per_char = word.chars().enumerate().map(|(i, c)| {
+        let rules = opts
+            .transforms
+            .iter()
+            .filter(|r| r.applies_in(c, i, len, place));
+        rules.count() as u128
+    });
+    per_char.fold(vec![1], |acc, subs| times(&acc, &[1, subs], opts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|w| w.to_string()).collect()
     }
 
-    Ok(())
+    fn options(transforms: &str, min_length: usize, max_length: usize) -> Options {
+        let transforms = transforms
+            .split(',')
+            .filter(|rule| !rule.is_empty())
+            .map(|rule| {
+                let (from, to) = rule.split_once('=').unwrap();
+                let (from, anchor) = match (from.strip_prefix('^'), from.strip_suffix('$')) {
+                    (Some(from), _) => (from, Anchor::Start),
+                    (_, Some(from)) => (from, Anchor::End),
+                    _ => (from, Anchor::Anywhere),
+                };
+                ReplacePair(from.parse().unwrap(), to.parse().unwrap(), anchor)
+            })
+            .collect();
+        Options {
+            transforms,
+            min_length,
+            max_length,
+            ..Options::default()
+        }
+    }
+
+    // every word generate_wordlist writes, in order, stopping after `limit`
+    fn generate_limited(
+        dictionary: &[&str],
+        terms: &[&str],
+        opts: &Options,
+        limit: Option<usize>,
+    ) -> io::Result<Vec<String>> {
+        let mut out = Vec::new();
+        let mut writer = Emitter::new(&mut out);
+        writer.limit = limit;
+        generate_wordlist(&mut writer, &words(dictionary), &words(terms), opts)?;
+        drop(writer);
+        let text = String::from_utf8(out).unwrap();
+        Ok(text.lines().map(str::to_string).collect())
+    }
+
+    #[test]
+    fn casing_masks_cover_long_words() {
+        let opts = options("", 1, 200);
+        let word = "abcdefghijklmnopqrstuvwxyzabcde";
+        let out = generate_limited(&[], &[word], &opts, Some(3)).unwrap();
+        assert_eq!(
+            out,
+            [
+                word,
+                &format!("A{}", &word[1..]),
+                &format!("aB{}", &word[2..])
+            ]
+        );
+        let word = "abcdefghijklmnopqrstuvwxyzabcdefghij";
+        assert_eq!(
+            generate_limited(&[], &[word], &opts, Some(2))
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let too_long = "a".repeat(128);
+        let err = generate_limited(&[], &[&too_long], &opts, Some(1)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn audit_positions_follow_expanding_chars() {
+        let opts = options("a=@", 1, 10);
+        let mut audit = Vec::new();
+        let mut writer = Emitter::new(io::sink());
+        writer.audit(&mut audit);
+        generate_wordlist(&mut writer, &[], &words(&["ßa"]), &opts).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        let audit = String::from_utf8(audit).unwrap();
+        let line = audit
+            .lines()
+            .find(|line| line.starts_with("{\"word\":\"SS@\""))
+            .unwrap();
+        assert!(line.contains("\"upper\":[0]"), "{}", line);
+        assert!(
+            line.contains("\"leet\":[{\"pos\":2,\"from\":\"a\",\"to\":\"@\"}]"),
+            "{}",
+            line
+        );
+    }
+
+    #[test]
+    fn estimate_matches_generated() {
+        // words with digits, separators, capitals and repeated letters for every rule kind
+        let dictionary = ["ad", "pass", "s_a", "1o", "Zoe"];
+        let terms = ["ac", "IT", "l"];
+        let base = options("a=@,s=$,o=0,a=4", 1, 6);
+        let cases = [
+            ("default", base.clone()),
+            ("anchored", options("^a=@,s$=z,o=0", 1, 6)),
+            (
+                "anchored snake",
+                Options {
+                    join_style: JoinStyle::Snake,
+                    ..options("^a=@,s$=z,o=0,_=-", 2, 8)
+                },
+            ),
+            (
+                "max mutations 1",
+                Options {
+                    max_mutations: Some(1),
+                    ..base.clone()
+                },
+            ),
+            (
+                "max mutations 2",
+                Options {
+                    max_mutations: Some(2),
+                    ..base.clone()
+                },
+            ),
+            (
+                "replaced only",
+                Options {
+                    replaced_only: true,
+                    ..base.clone()
+                },
+            ),
+            (
+                "max leet subs",
+                Options {
+                    max_leet_subs: Some(1),
+                    ..base.clone()
+                },
+            ),
+            (
+                "preserve case",
+                Options {
+                    preserve_base_case: true,
+                    ..base.clone()
+                },
+            ),
+            (
+                "terms preserve case",
+                Options {
+                    terms_preserve_case: true,
+                    join_style: JoinStyle::Camel,
+                    ..base.clone()
+                },
+            ),
+            (
+                "title",
+                Options {
+                    join_style: JoinStyle::Title,
+                    max_mutations: Some(1),
+                    ..base.clone()
+                },
+            ),
+            (
+                "no recurse, no repeats",
+                Options {
+                    no_recurse: true,
+                    no_repeat_terms: true,
+                    dictionary_first: true,
+                    ..base.clone()
+                },
+            ),
+            (
+                "doubling",
+                Options {
+                    double_chars: true,
+                    doubled: true,
+                    ..base.clone()
+                },
+            ),
+            (
+                "min length 0",
+                Options {
+                    max_part_length: Some(3),
+                    max_concat_length: Some(9),
+                    ..options("a=@,s=$", 0, 5)
+                },
+            ),
+        ];
+        let mut cases = cases.to_vec();
+        for mode in [CapitalizeAfter::Separator, CapitalizeAfter::Digit] {
+            for max_mutations in [None, Some(1), Some(2), Some(3)] {
+                for join_style in [JoinStyle::Plain, JoinStyle::Camel, JoinStyle::Snake] {
+                    for transforms in ["a=@,s=$,o=0,a=4", "^a=@,s$=z,A=4,_=-"] {
+                        let opts = Options {
+                            capitalize_after: Some(mode),
+                            max_mutations,
+                            join_style,
+                            max_leet_subs: max_mutations.map(|_| 1),
+                            ..options(transforms, 1, 6)
+                        };
+                        cases.push(("capitalize after", opts));
+                    }
+                }
+            }
+        }
+        for join_style in [JoinStyle::Camel, JoinStyle::Title] {
+            for max_mutations in [None, Some(2), Some(3)] {
+                for transforms in ["a=@,s=$,o=0,a=4", "^a=@,s$=z,A=4,P=9"] {
+                    let opts = Options {
+                        join_style,
+                        max_mutations,
+                        ..options(transforms, 1, 6)
+                    };
+                    cases.push(("styled join", opts));
+                }
+            }
+        }
+        for (name, opts) in cases {
+            let generated = generate_limited(&dictionary, &terms, &opts, None).unwrap();
+            let estimate = estimate_permutation_count(&words(&dictionary), &words(&terms), &opts);
+            assert_eq!(estimate, generated.len() as u128, "{}: {:?}", name, opts);
+        }
+    }
+
+    // every chain of parts, dictionary words first then terms, the way generate_concats
+    // is meant to reach them
+    fn brute_force_concats(dictionary: &[&str], terms: &[&str], opts: &Options) -> Vec<String> {
+        fn tails(head: &str, parts: &[&str], opts: &Options, out: &mut Vec<String>) {
+            for part in parts {
+                let joined = format!("{}{}{}", head, opts.separator(), part);
+                if joined.len() > opts.max_length {
+                    continue;
+                }
+                if joined.len() >= opts.min_length {
+                    out.push(joined.clone());
+                }
+                tails(&joined, parts, opts, out);
+            }
+        }
+        let mut out = Vec::new();
+        for (firsts, others) in [
+            (dictionary, [dictionary, terms]),
+            (terms, [terms, dictionary]),
+        ] {
+            for first in firsts {
+                if first.len() >= opts.min_length {
+                    out.push(first.to_string());
+                }
+                for parts in others {
+                    tails(first, parts, opts, &mut out);
+                }
+            }
+        }
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn concats_reach_every_chain_in_range() {
+        let dictionary = ["ab", "c"];
+        for terms in [&[][..], &["xyz"], &["d", "efg"]] {
+            for join_style in [JoinStyle::Plain, JoinStyle::Snake] {
+                for min_length in 1..=7 {
+                    for max_length in min_length.max(2)..=8 {
+                        // no casing or leet on the concatenations, and only the lowercase
+                        // casing of single words
+                        let opts = Options {
+                            join_style,
+                            max_mutations: Some(1),
+                            ..options("", min_length, max_length)
+                        };
+                        let mut generated: Vec<String> =
+                            generate_limited(&dictionary, terms, &opts, None)
+                                .unwrap()
+                                .into_iter()
+                                .filter(|w| *w == w.to_lowercase())
+                                .collect();
+                        generated.sort();
+                        let expected = brute_force_concats(&dictionary, terms, &opts);
+                        assert_eq!(generated, expected, "{:?} {:?}", terms, opts);
+                    }
+                }
+            }
+        }
+    }
+
+    // the original add_transformations: every combination of the chars so far, rebuilt
+    // for each char
+    fn collected_variants(word: &str, opts: &Options) -> Vec<String> {
+        let len = word.chars().count();
+        let mut current = vec![String::new()];
+        for (i, c) in word.chars().enumerate() {
+            let mut new_combinations = Vec::new();
+            for combo in current.iter() {
+                new_combinations.push(combo.clone() + &c.to_string());
+                for r in &opts.transforms {
+                    if r.applies(c, i, len) {
+                        new_combinations.push(combo.clone() + &r.to().to_string());
+                    }
+                }
+            }
+            current = new_combinations;
+        }
+        current
+    }
+
+    #[test]
+    fn variants_match_collected_combinations() {
+        let rule_sets = [
+            "a=@,s=$,o=0",
+            "a=@,a=4,s=$,s=5,i=!,i=1",
+            "^p=P,s$=z,o=0,e=3",
+            "x=y",
+        ];
+        let words = ["password", "assassins", "p", "", "xylophone", "aaaa", "ß_é"];
+        for rules in rule_sets {
+            for word in words {
+                let opts = options(rules, 0, 20);
+                let mut out = Vec::new();
+                let mut writer = Emitter::new(&mut out);
+                let parts = [word];
+                let provenance = Provenance {
+                    source: Source {
+                        kind: SourceKind::Word,
+                        parts: &parts,
+                    },
+                    base: word,
+                    cased: word,
+                    flipped: &[],
+                    leet: &[],
+                };
+                add_transformations(&mut writer, &provenance, &opts, true).unwrap();
+                writer.flush().unwrap();
+                drop(writer);
+                let expected: String = collected_variants(word, &opts)
+                    .iter()
+                    .map(|variant| format!("{}\n", variant))
+                    .collect();
+                assert_eq!(
+                    String::from_utf8(out).unwrap(),
+                    expected,
+                    "{} {}",
+                    rules,
+                    word
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn doubling_repeats_each_char_once() {
+        assert_eq!(double_chars("ab"), ["aab", "abb"]);
+        assert_eq!(double_chars("cat"), ["ccat", "caat", "catt"]);
+        // a run only doubles once, at its end
+        assert_eq!(double_chars("aab"), ["aaab", "aabb"]);
+
+        let opts = Options {
+            double_chars: true,
+            max_mutations: Some(1),
+            ..options("", 1, 3)
+        };
+        let out = generate_limited(&[], &["ab"], &opts, None).unwrap();
+        for word in ["ab", "aab", "abb"] {
+            assert_eq!(out.iter().filter(|w| *w == word).count(), 1, "{}", word);
+        }
+        // doubling used up the one mutation, so there is no casing on top
+        assert!(!out.iter().any(|w| w.len() == 3 && *w != w.to_lowercase()));
+    }
+
+    #[test]
+    fn long_parts_are_kept_out_of_concatenations() {
+        let opts = Options {
+            max_part_length: Some(3),
+            max_mutations: Some(1),
+            ..options("", 1, 20)
+        };
+        let out = generate_limited(&["password"], &["ab"], &opts, None).unwrap();
+        assert!(out.iter().any(|w| w == "password"));
+        assert!(out.iter().any(|w| w == "abab"));
+        assert!(!out.iter().any(|w| w.len() > 8 && w.contains("password")));
+        assert!(!out
+            .iter()
+            .any(|w| w.contains("passwordab") || w.contains("abpassword")));
+    }
+
+    #[test]
+    fn casing_is_unicode_aware() {
+        let opts = options("", 1, 5);
+        let out = generate_limited(&[], &["café"], &opts, None).unwrap();
+        assert!(out.iter().any(|w| w == "CAFÉ"));
+        assert!(out.iter().any(|w| w == "cafÉ"));
+        assert_eq!(out.len(), 16);
+
+        // uppercasing ß lengthens the word, variants past the max length still come out
+        let out = generate_limited(&[], &["ßa"], &options("", 1, 2), None).unwrap();
+        assert_eq!(out, ["ßa", "SSa", "ßA", "SSA"]);
+    }
+
+    #[test]
+    fn no_recurse_stops_at_pairs() {
+        let opts = Options {
+            no_recurse: true,
+            max_mutations: Some(1),
+            ..options("", 1, 20)
+        };
+        let out = generate_limited(&["ab", "cd"], &["ef"], &opts, None).unwrap();
+        assert!(out.iter().any(|w| w == "abcd"));
+        assert!(out.iter().any(|w| w == "efab"));
+        assert!(!out.iter().any(|w| w.len() > 4));
+
+        let opts = Options {
+            no_recurse: false,
+            ..opts
+        };
+        let out = generate_limited(&["ab", "cd"], &["ef"], &opts, None).unwrap();
+        assert!(out.iter().any(|w| w == "abcdab"));
+    }
+
+    #[test]
+    fn anchored_rules_only_touch_the_ends() {
+        let opts = Options {
+            max_mutations: Some(1),
+            ..options("^s=$,s$=z", 1, 6)
+        };
+        let out = generate_limited(&[], &["sass"], &opts, None).unwrap();
+        let leet: Vec<&String> = out
+            .iter()
+            .filter(|w| *w != "sass" && *w == &w.to_lowercase())
+            .collect();
+        assert_eq!(leet, ["sasz", "$ass", "$asz"]);
+        assert!(!out.iter().any(|w| w.contains("a$") || w.contains("zs")));
+    }
+
+    #[test]
+    fn join_styles_shape_the_parts() {
+        let joined = |join_style| {
+            let opts = Options {
+                join_style,
+                no_recurse: true,
+                max_mutations: Some(1),
+                ..options("", 1, 20)
+            };
+            let mut out = generate_limited(&[], &["admin", "pass"], &opts, None).unwrap();
+            out.retain(|w| w.len() > 5);
+            out.sort();
+            out
+        };
+        assert_eq!(
+            joined(JoinStyle::Plain),
+            ["adminadmin", "adminpass", "passadmin", "passpass"]
+        );
+        // the lowercase join is still written, the style only adds the form it shapes
+        let plain = joined(JoinStyle::Plain);
+        let styled = |join_style, forms: [&str; 4]| {
+            let mut expected: Vec<String> = words(&forms);
+            expected.extend(plain.iter().cloned());
+            expected.sort();
+            assert_eq!(joined(join_style), expected);
+        };
+        styled(
+            JoinStyle::Camel,
+            ["adminAdmin", "adminPass", "passAdmin", "passPass"],
+        );
+        styled(
+            JoinStyle::Title,
+            ["AdminAdmin", "AdminPass", "PassAdmin", "PassPass"],
+        );
+        assert_eq!(
+            joined(JoinStyle::Snake),
+            ["admin_admin", "admin_pass", "pass_admin", "pass_pass"]
+        );
+    }
+
+    #[test]
+    fn must_contain_keeps_only_matching_words() {
+        let opts = Options {
+            max_mutations: Some(2),
+            ..options("a=@,o=0", 1, 8)
+        };
+        let dictionary = ["admin", "root", "pass"];
+        let terms = ["x1", "ad"];
+        let all = generate_limited(&dictionary, &terms, &opts, None).unwrap();
+        for needle in ["@d", "R00", "ssx"] {
+            let opts = Options {
+                must_contain: vec![needle.to_string()],
+                ..opts.clone()
+            };
+            let out = generate_limited(&dictionary, &terms, &opts, None).unwrap();
+            let expected: Vec<&String> = all.iter().filter(|w| w.contains(needle)).collect();
+            assert!(!expected.is_empty(), "{}", needle);
+            // pruning whole words never drops a variant that would have matched
+            assert_eq!(out.iter().collect::<Vec<_>>(), expected, "{}", needle);
+        }
+    }
+
+    #[test]
+    fn concatenations_have_their_own_length_budget() {
+        let opts = Options {
+            max_concat_length: Some(9),
+            max_mutations: Some(1),
+            ..options("", 1, 4)
+        };
+        let out = generate_limited(&["abcd"], &["xyz"], &opts, None).unwrap();
+        assert!(out.iter().any(|w| w == "abcdxyz"));
+        assert!(out.iter().any(|w| w == "xyzxyzxyz"));
+        assert!(out.iter().all(|w| w.len() <= 9));
+        assert!(!out.iter().any(|w| w.len() == 5));
+
+        // without it concatenations keep to the max length as well
+        let opts = Options {
+            max_concat_length: None,
+            ..opts
+        };
+        let out = generate_limited(&["abcd"], &["xyz"], &opts, None).unwrap();
+        assert!(out.iter().all(|w| w.len() <= 4));
+    }
+
+    #[test]
+    fn one_mutation_never_stacks_casing_and_leet() {
+        let opts = Options {
+            max_mutations: Some(1),
+            ..options("a=@,s=$,o=0", 1, 12)
+        };
+        let out = generate_limited(&["boss"], &["as"], &opts, None).unwrap();
+        let leet = |w: &&String| w.contains(['@', '$', '0']);
+        let cased = |w: &&String| w.chars().any(char::is_uppercase);
+        assert!(out.iter().any(|w| leet(&w)));
+        assert!(out.iter().any(|w| cased(&w)));
+        assert!(!out.iter().any(|w| leet(&w) && cased(&w)));
+
+        // two allow both on a single word
+        let opts = Options {
+            max_mutations: Some(2),
+            ..opts
+        };
+        let out = generate_limited(&["boss"], &["as"], &opts, None).unwrap();
+        assert!(out.iter().any(|w| w == "B0ss"));
+    }
+
+    #[test]
+    fn audit_records_how_each_word_was_made() {
+        let opts = Options {
+            max_mutations: Some(2),
+            ..options("a=@", 1, 4)
+        };
+        let mut audit = Vec::new();
+        let mut out = Vec::new();
+        let mut writer = Emitter::new(&mut out);
+        writer.audit(&mut audit);
+        generate_wordlist(&mut writer, &words(&["ab"]), &words(&["cd"]), &opts).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        let (audit, out) = (
+            String::from_utf8(audit).unwrap(),
+            String::from_utf8(out).unwrap(),
+        );
+        // one record per word, in the order written
+        let logged: Vec<&str> = audit
+            .lines()
+            .map(|line| {
+                let rest = line.strip_prefix("{\"word\":\"").unwrap();
+                &rest[..rest.find('"').unwrap()]
+            })
+            .collect();
+        assert_eq!(logged, out.lines().collect::<Vec<_>>());
+
+        let record = |word: &str| {
+            let prefix = format!("{{\"word\":\"{}\",", word);
+            audit
+                .lines()
+                .find(|line| line.starts_with(&prefix))
+                .unwrap()
+        };
+        assert_eq!(
+            record("@b"),
+            "{\"word\":\"@b\",\"source\":\"word\",\"parts\":[\"ab\"],\"base\":\"ab\",\
+             \"cased\":\"ab\",\"upper\":[],\"leet\":[{\"pos\":0,\"from\":\"a\",\"to\":\"@\"}]}"
+        );
+        assert_eq!(
+            record("Cd"),
+            "{\"word\":\"Cd\",\"source\":\"word\",\"parts\":[\"cd\"],\"base\":\"cd\",\
+             \"cased\":\"Cd\",\"upper\":[0],\"leet\":[]}"
+        );
+        assert_eq!(
+            record("@bcd"),
+            "{\"word\":\"@bcd\",\"source\":\"concat\",\"parts\":[\"ab\",\"cd\"],\"base\":\"abcd\",\
+             \"cased\":\"abcd\",\"upper\":[],\"leet\":[{\"pos\":0,\"from\":\"a\",\"to\":\"@\"}]}"
+        );
+    }
+
+    #[test]
+    fn empty_term_is_the_empty_string() {
+        let opts = options("a=@", 0, 4);
+        let out = generate_limited(&[], &["", "ab"], &opts, None).unwrap();
+        assert_eq!(out.iter().filter(|w| w.is_empty()).count(), 1);
+        assert!(out.iter().any(|w| w == "abab"));
+        // the empty part is never joined, so nothing repeats
+        let mut unique = out.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), out.len());
+        assert_eq!(
+            estimate_permutation_count(&[], &words(&["", "ab"]), &opts),
+            out.len() as u128
+        );
+
+        // with a min of 1 it's left out
+        let out = generate_limited(&[], &["", "ab"], &options("a=@", 1, 4), None).unwrap();
+        assert!(!out.iter().any(|w| w.is_empty()));
+    }
+
+    #[test]
+    fn presets_match_their_help() {
+        // the help each preset shows in --help, as (from, to) pairs
+        let documented = |preset: LeetPreset| -> Vec<(char, char)> {
+            let value = preset.to_possible_value().unwrap();
+            let help = value.get_help().unwrap().to_string();
+            let (mut pairs, list) = match help.strip_prefix("common plus ") {
+                Some(list) => (DEFAULT_REPLACEMENTS.to_vec(), list.to_string()),
+                None => (
+                    Vec::new(),
+                    help.trim_start_matches("digits only: ").to_string(),
+                ),
+            };
+            for pair in list.split(',') {
+                let (from, to) = pair.split_once('=').unwrap();
+                pairs.push((from.parse().unwrap(), to.parse().unwrap()));
+            }
+            pairs
+        };
+        for &preset in LeetPreset::value_variants() {
+            assert_eq!(preset.pairs(), documented(preset), "{:?}", preset);
+        }
+        assert!(LeetPreset::Numeric
+            .pairs()
+            .iter()
+            .all(|(_, to)| to.is_ascii_digit()));
+    }
+
+    #[test]
+    fn capitalize_after_boundaries() {
+        let opts = Options {
+            capitalize_after: Some(CapitalizeAfter::Separator),
+            ..options("", 1, 20)
+        };
+        let out = generate_limited(&[], &["admin_login"], &opts, None).unwrap();
+        assert_eq!(out, ["admin_login", "Admin_Login"]);
+
+        let opts = Options {
+            capitalize_after: Some(CapitalizeAfter::Digit),
+            ..opts
+        };
+        let out = generate_limited(&[], &["admin1login_x"], &opts, None).unwrap();
+        assert_eq!(out, ["admin1login_x", "Admin1Login_x"]);
+        // nothing to capitalize leaves the one form
+        let opts = Options {
+            max_length: 3,
+            ..opts
+        };
+        let out = generate_limited(&[], &["123"], &opts, None).unwrap();
+        assert_eq!(out, ["123"]);
+    }
+
+    #[test]
+    fn no_repeat_terms_never_joins_a_word_to_itself() {
+        let opts = Options {
+            no_repeat_terms: true,
+            max_mutations: Some(1),
+            ..options("", 1, 12)
+        };
+        let out = generate_limited(&["cat"], &["ab", "cd", "ef"], &opts, None).unwrap();
+        for word in ["abcd", "cdab", "abcdef", "efcdab", "catab", "abcat"] {
+            assert!(out.iter().any(|w| w == word), "{}", word);
+        }
+        for part in ["ab", "cd", "ef", "cat"] {
+            let twice = |w: &&String| w.matches(part).count() > 1;
+            assert!(!out.iter().any(|w| twice(&w)), "{}", part);
+        }
+
+        let opts = Options {
+            no_repeat_terms: false,
+            ..opts
+        };
+        let out = generate_limited(&["cat"], &["ab", "cd", "ef"], &opts, None).unwrap();
+        assert!(out.iter().any(|w| w == "abab"));
+        assert!(out.iter().any(|w| w == "abcdab"));
+    }
+
+    #[test]
+    fn cross_list_joins_go_both_ways() {
+        let opts = Options {
+            no_recurse: true,
+            max_mutations: Some(1),
+            ..options("", 1, 10)
+        };
+        let out = generate_limited(&["admin"], &["root"], &opts, None).unwrap();
+        assert!(out.iter().any(|w| w == "adminroot"));
+        assert!(out.iter().any(|w| w == "rootadmin"));
+
+        let opts = Options {
+            dictionary_first: true,
+            ..opts
+        };
+        let out = generate_limited(&["admin"], &["root"], &opts, None).unwrap();
+        assert!(out.iter().any(|w| w == "adminroot"));
+        assert!(!out.iter().any(|w| w == "rootadmin"));
+    }
+
+    #[test]
+    fn restricted_casing_keeps_the_word_as_given() {
+        let single = |opts: Options| {
+            let opts = Options {
+                max_length: 8,
+                ..opts
+            };
+            generate_limited(&[], &["McDonald"], &opts, None).unwrap()
+        };
+        // every casing already includes the original
+        let out = single(options("", 1, 8));
+        assert_eq!(out.iter().filter(|w| *w == "McDonald").count(), 1);
+        assert_eq!(out.len(), 256);
+
+        let out = single(Options {
+            max_mutations: Some(0),
+            ..options("a=@", 1, 8)
+        });
+        assert_eq!(out, ["mcdonald", "McDonald"]);
+
+        let out = single(Options {
+            max_mutations: Some(1),
+            capitalize_after: Some(CapitalizeAfter::Separator),
+            ..options("a=@", 1, 8)
+        });
+        // the word as given isn't a casing mutation, so it can still get leet
+        assert_eq!(
+            out,
+            ["mcdonald", "mcdon@ld", "Mcdonald", "McDonald", "McDon@ld"]
+        );
+    }
+
+    #[test]
+    fn doubled_only_joins_each_word_with_itself() {
+        let opts = Options {
+            doubled: true,
+            max_mutations: Some(1),
+            ..options("", 1, 8)
+        };
+        let out = generate_limited(&["cat", "dog"], &[], &opts, None).unwrap();
+        let joined: Vec<&String> = out.iter().filter(|w| w.len() > 3).collect();
+        assert_eq!(joined, ["catcat", "dogdog"]);
+
+        let cased = Options {
+            max_mutations: None,
+            ..opts.clone()
+        };
+        let out = generate_limited(&["cat", "dog"], &[], &cased, None).unwrap();
+        assert!(out.iter().any(|w| w == "CatCat"));
+
+        // longer than the max length once doubled
+        let out = generate_limited(&["cat", "horse"], &[], &opts, None).unwrap();
+        assert!(!out.iter().any(|w| w.to_lowercase() == "horsehorse"));
+    }
+
+    #[test]
+    fn uncased_chars_add_no_casings() {
+        let opts = options("", 1, 6);
+        let out = generate_limited(&[], &["a1b2"], &opts, None).unwrap();
+        assert_eq!(out, ["a1b2", "A1b2", "a1B2", "A1B2"]);
+        let out = generate_limited(&[], &["1234"], &opts, None).unwrap();
+        assert_eq!(out, ["1234"]);
+    }
+
+    #[test]
+    fn max_leet_subs_caps_replacements_per_word() {
+        let subs = |word: &str| word.chars().filter(|c| "@$0".contains(*c)).count();
+        let opts = Options {
+            max_leet_subs: Some(1),
+            max_mutations: Some(1),
+            ..options("a=@,s=$,o=0", 1, 6)
+        };
+        let out = generate_limited(&[], &["sassoo"], &opts, None).unwrap();
+        assert!(out.iter().all(|w| subs(w) <= 1));
+        // each of the six positions replaced on its own
+        let leet: Vec<&String> = out.iter().filter(|w| subs(w) == 1).collect();
+        assert_eq!(leet.len(), 6);
+
+        let opts = Options {
+            max_leet_subs: Some(2),
+            ..opts
+        };
+        let out = generate_limited(&[], &["sassoo"], &opts, None).unwrap();
+        assert!(out.iter().all(|w| subs(w) <= 2));
+        // and every pair of them
+        assert_eq!(out.iter().filter(|w| subs(w) == 2).count(), 15);
+    }
+
+    #[test]
+    fn preserve_base_case_flips_from_the_input() {
+        let opts = Options {
+            preserve_base_case: true,
+            ..options("", 1, 6)
+        };
+        let out = generate_limited(&[], &["iPhone"], &opts, None).unwrap();
+        // the base variant keeps the internal capital, the masks flip letters from it
+        assert_eq!(&out[..3], ["iPhone", "IPhone", "iphone"]);
+        assert_eq!(out.len(), 64);
+
+        let out = generate_limited(&[], &["iPhone"], &options("", 1, 6), None).unwrap();
+        assert_eq!(&out[..2], ["iphone", "Iphone"]);
+    }
+
+    #[test]
+    fn terms_preserve_case_writes_terms_as_given() {
+        let opts = Options {
+            terms_preserve_case: true,
+            max_mutations: Some(1),
+            ..options("", 1, 8)
+        };
+        let out = generate_limited(&["corp"], &["ACME"], &opts, None).unwrap();
+        assert_eq!(out[..2], ["corp", "Corp"]);
+        assert!(out.iter().any(|w| w == "ACME"));
+        assert!(out.iter().any(|w| w == "corpacme"));
+
+        let opts = Options {
+            terms_preserve_case: false,
+            max_mutations: Some(0),
+            ..opts
+        };
+        let out = generate_limited(&[], &["ACME"], &opts, None).unwrap();
+        assert_eq!(out, ["acme", "ACME"]);
+    }
+
+    #[test]
+    fn styled_joins_are_cased_from_the_style() {
+        let joined = |join_style, max_mutations| {
+            let opts = Options {
+                join_style,
+                max_mutations,
+                no_recurse: true,
+                ..options("a=@", 1, 20)
+            };
+            generate_limited(&[], &["admin", "pass"], &opts, None).unwrap()
+        };
+        // every casing is written whatever the style, the styled join comes first
+        let first = |out: Vec<String>| {
+            let first = out
+                .iter()
+                .find(|w| w.to_lowercase() == "adminpass")
+                .cloned();
+            (first.unwrap(), out.len())
+        };
+        let (plain, count) = first(joined(JoinStyle::Plain, None));
+        assert_eq!(plain, "adminpass");
+        assert_eq!(
+            first(joined(JoinStyle::Camel, None)),
+            ("adminPass".into(), count)
+        );
+        assert_eq!(
+            first(joined(JoinStyle::Title, None)),
+            ("AdminPass".into(), count)
+        );
+
+        // and it's the unmutated form, so it's the one leet still applies to
+        let plain = joined(JoinStyle::Plain, Some(2));
+        assert!(plain.contains(&"@dminp@ss".to_string()));
+        assert!(!plain.contains(&"@dminP@ss".to_string()));
+        let camel = joined(JoinStyle::Camel, Some(2));
+        assert!(camel.contains(&"@dminP@ss".to_string()));
+        assert!(!camel.contains(&"@dminp@ss".to_string()));
+        assert!(camel.contains(&"adminpass".to_string()));
+    }
 }
@@ -0,0 +1,329 @@
+use clap::ValueEnum;
+
+/// Digests that can be emitted in place of plaintext words
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HashAlgo {
+    Md5,
+    Sha1,
+    /// MD4 of the UTF-16LE encoded word
+    Ntlm,
+}
+
+impl HashAlgo {
+    /// Lowercase hex digest of `word`
+    pub fn hex(&self, word: &str) -> String {
+        let digest = match self {
+            HashAlgo::Md5 => md5(word.as_bytes()).to_vec(),
+            HashAlgo::Sha1 => sha1(word.as_bytes()).to_vec(),
+            HashAlgo::Ntlm => {
+                let utf16: Vec<u8> = word.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                md4(&utf16).to_vec()
+            }
+        };
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+// Merkle–Damgård padding shared by all three: 0x80, zeros, then the bit length
+fn pad(data: &[u8], big_endian: bool) -> Vec<u8> {
+    let bits = (data.len() as u64).wrapping_mul(8);
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    if big_endian {
+        msg.extend_from_slice(&bits.to_be_bytes());
+    } else {
+        msg.extend_from_slice(&bits.to_le_bytes());
+    }
+    msg
+}
+
+fn le_words(block: &[u8]) -> [u32; 16] {
+    let mut x = [0u32; 16];
+    for (i, word) in block.chunks(4).enumerate() {
+        x[i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    x
+}
+
+fn le_digest(state: [u32; 4]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for (i, v) in state.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&v.to_le_bytes());
+    }
+    out
+}
+
+fn md5(data: &[u8]) -> [u8; 16] {
+    const S: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5,
+        9, 14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10,
+        15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    // K[i] = floor(abs(sin(i + 1)) * 2^32)
+    let k: Vec<u32> = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32)
+        .collect();
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for block in pad(data, false).chunks(64) {
+        let m = le_words(block);
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(k[i]).wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(S[i]));
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+    le_digest(state)
+}
+
+fn md4(data: &[u8]) -> [u8; 16] {
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for block in pad(data, false).chunks(64) {
+        let x = le_words(block);
+        let [mut a, mut b, mut c, mut d] = state;
+
+        for &i in &[0, 4, 8, 12] {
+            a = (a.wrapping_add((b & c) | (!b & d)).wrapping_add(x[i])).rotate_left(3);
+            d = (d.wrapping_add((a & b) | (!a & c)).wrapping_add(x[i + 1])).rotate_left(7);
+            c = (c.wrapping_add((d & a) | (!d & b)).wrapping_add(x[i + 2])).rotate_left(11);
+            b = (b.wrapping_add((c & d) | (!c & a)).wrapping_add(x[i + 3])).rotate_left(19);
+        }
+        let g = |x: u32, y: u32, z: u32| (x & y) | (x & z) | (y & z);
+        for i in 0..4 {
+            a = (a
+                .wrapping_add(g(b, c, d))
+                .wrapping_add(x[i])
+                .wrapping_add(0x5a827999))
+            .rotate_left(3);
+            d = (d
+                .wrapping_add(g(a, b, c))
+                .wrapping_add(x[i + 4])
+                .wrapping_add(0x5a827999))
+            .rotate_left(5);
+            c = (c
+                .wrapping_add(g(d, a, b))
+                .wrapping_add(x[i + 8])
+                .wrapping_add(0x5a827999))
+            .rotate_left(9);
+            b = (b
+                .wrapping_add(g(c, d, a))
+                .wrapping_add(x[i + 12])
+                .wrapping_add(0x5a827999))
+            .rotate_left(13);
+        }
+        for &i in &[0, 2, 1, 3] {
+            a = (a
+                .wrapping_add(b ^ c ^ d)
+                .wrapping_add(x[i])
+                .wrapping_add(0x6ed9eba1))
+            .rotate_left(3);
+            d = (d
+                .wrapping_add(a ^ b ^ c)
+                .wrapping_add(x[i + 8])
+                .wrapping_add(0x6ed9eba1))
+            .rotate_left(9);
+            c = (c
+                .wrapping_add(d ^ a ^ b)
+                .wrapping_add(x[i + 4])
+                .wrapping_add(0x6ed9eba1))
+            .rotate_left(11);
+            b = (b
+                .wrapping_add(c ^ d ^ a)
+                .wrapping_add(x[i + 12])
+                .wrapping_add(0x6ed9eba1))
+            .rotate_left(15);
+        }
+
+        for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+    le_digest(state)
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+    for block in pad(data, true).chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &wi) in w.iter().enumerate() {
+            let (f, k) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5a827999),
+                1 => (b ^ c ^ d, 0x6ed9eba1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(wi);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (s, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+    let mut out = [0u8; 20];
+    for (i, v) in h.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&v.to_be_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(digest: &[u8]) -> String {
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    const ALPHANUMERIC: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+    #[test]
+    fn md5_rfc_1321() {
+        let digits = "1234567890".repeat(8);
+        let cases = [
+            ("", "d41d8cd98f00b204e9800998ecf8427e"),
+            ("a", "0cc175b9c0f1b6a831c399e269772661"),
+            ("abc", "900150983cd24fb0d6963f7d28e17f72"),
+            ("message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+            (
+                "abcdefghijklmnopqrstuvwxyz",
+                "c3fcd3d76192e4007dfb496cca67e13b",
+            ),
+            (ALPHANUMERIC, "d174ab98d277d9f5a5611c2c9f419d9f"),
+            (&digits, "57edf4a22be3c955ac49da2e2107b67a"),
+        ];
+        for (input, digest) in cases {
+            assert_eq!(HashAlgo::Md5.hex(input), digest, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn md4_rfc_1320() {
+        let digits = "1234567890".repeat(8);
+        let cases = [
+            ("", "31d6cfe0d16ae931b73c59d7e0c089c0"),
+            ("a", "bde52cb31de33e46245e05fbdbd6fb24"),
+            ("abc", "a448017aaf21d8525fc10ae87aa6729d"),
+            ("message digest", "d9130a8164549fe818874806e1c7014b"),
+            (
+                "abcdefghijklmnopqrstuvwxyz",
+                "d79e1c308aa5bbcdeea8ed63df412da9",
+            ),
+            (ALPHANUMERIC, "043f8582f241db351ce627e153e7f0e4"),
+            (&digits, "e33b4ddc9c38f2199c3e7b164fcc0536"),
+        ];
+        for (input, digest) in cases {
+            assert_eq!(hex(&md4(input.as_bytes())), digest, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn sha1_rfc_3174() {
+        let cases = [
+            ("", "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            ("abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+            ),
+            (
+                &"0123456701234567012345670123456701234567012345670123456701234567".repeat(10),
+                "dea356a2cddd90c7a7ecedc5ebb563934f460452",
+            ),
+        ];
+        for (input, digest) in cases {
+            assert_eq!(HashAlgo::Sha1.hex(input), digest, "{:?}", input);
+        }
+    }
+
+    // 55 bytes is the longest message whose padding fits in one block
+    #[test]
+    fn padding_block_boundaries() {
+        let cases = [
+            (
+                55,
+                "ef1772b6dff9a122358552954ad0df65",
+                "c1c8bbdc22796e28c0e15163d20899b65621d65a",
+            ),
+            (
+                56,
+                "3b0c8ac703f828b04c6c197006d17218",
+                "c2db330f6083854c99d4b5bfb6e8f29f201be699",
+            ),
+            (
+                63,
+                "b06521f39153d618550606be297466d5",
+                "03f09f5b158a7a8cdad920bddc29b81c18a551f5",
+            ),
+            (
+                64,
+                "014842d480b571495a4a0363793f7367",
+                "0098ba824b5c16427bd7a1122a5a442a25ec644d",
+            ),
+            (
+                65,
+                "c743a45e0d2e6a95cb859adae0248435",
+                "11655326c708d70319be2610e8a57d9a5b959d3b",
+            ),
+            (
+                119,
+                "8a7bd0732ed6a28ce75f6dabc90e1613",
+                "ee971065aaa017e0632a8ca6c77bb3bf8b1dfc56",
+            ),
+            (
+                120,
+                "5f61c0ccad4cac44c75ff505e1f1e537",
+                "f34c1488385346a55709ba056ddd08280dd4c6d6",
+            ),
+        ];
+        for (len, md5, sha1) in cases {
+            let input = "a".repeat(len);
+            assert_eq!(pad(input.as_bytes(), true).len() % 64, 0);
+            assert_eq!(HashAlgo::Md5.hex(&input), md5, "{} bytes", len);
+            assert_eq!(HashAlgo::Sha1.hex(&input), sha1, "{} bytes", len);
+        }
+    }
+
+    #[test]
+    fn ntlm_hashes_utf16() {
+        assert_eq!(HashAlgo::Ntlm.hex(""), "31d6cfe0d16ae931b73c59d7e0c089c0");
+        assert_eq!(
+            HashAlgo::Ntlm.hex("password"),
+            "8846f7eaee8fb117ad06bdd830b7586c"
+        );
+        assert_eq!(
+            HashAlgo::Ntlm.hex("Password"),
+            "a4f49c406510bdcab6824ee7c30fd852"
+        );
+    }
+}
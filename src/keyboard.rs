@@ -0,0 +1,151 @@
+use crate::emit::Emitter;
+use clap::ValueEnum;
+use std::io;
+
+/// Keyboard layouts for `--keyboard-walks`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    #[default]
+    Qwerty,
+    Qwertz,
+    Azerty,
+    Dvorak,
+}
+
+impl Layout {
+    fn rows(&self) -> [&'static str; 4] {
+        match self {
+            Layout::Qwerty => ["1234567890-=", "qwertyuiop[]", "asdfghjkl;'", "zxcvbnm,./"],
+            Layout::Qwertz => ["1234567890ß", "qwertzuiopü+", "asdfghjklöä#", "yxcvbnm,.-"],
+            Layout::Azerty => [
+                "&é\"'(-è_çà)=",
+                "azertyuiop^$",
+                "qsdfghjklmù*",
+                "wxcvbn,;:!",
+            ],
+            Layout::Dvorak => ["1234567890[]", "',.pyfgcrl/=", "aoeuidhtns-", ";qjkxbmwvz"],
+        }
+    }
+}
+
+// rows are staggered, so besides left/right each key touches two keys in the row
+// above and two below: same index (i.e. `1` -> `q`) and one to the side
+const DIRECTIONS: [(isize, isize); 6] = [(0, 1), (0, -1), (1, 0), (1, -1), (-1, 0), (-1, 1)];
+
+/// Emit every straight walk across the keyboard between `min_length` and `max_length`
+/// keys, in each direction from each starting key (i.e. `qwe`, `ewq`, `1qaz`, `zaq1`).
+pub fn generate_walks(
+    writer: &mut Emitter,
+    layout: Layout,
+    min_length: usize,
+    max_length: usize,
+) -> io::Result<()> {
+    walks(layout, min_length, max_length, |walk| {
+        if writer.is_done() {
+            return Ok(false);
+        }
+        writer.emit(&walk)?;
+        Ok(true)
+    })
+}
+
+/// Number of walks `generate_walks` emits for the same arguments
+pub fn count_walks(layout: Layout, min_length: usize, max_length: usize) -> u128 {
+    let mut count = 0;
+    let _ = walks(layout, min_length, max_length, |_| {
+        count += 1;
+        Ok(true)
+    });
+    count
+}
+
+// call `f` with each walk in order until it returns false
+fn walks(
+    layout: Layout,
+    min_length: usize,
+    max_length: usize,
+    mut f: impl FnMut(String) -> io::Result<bool>,
+) -> io::Result<()> {
+    let rows: Vec<Vec<char>> = layout
+        .rows()
+        .iter()
+        .map(|row| row.chars().collect())
+        .collect();
+    let key = |r: isize, c: isize| -> Option<char> {
+        let row = rows.get(usize::try_from(r).ok()?)?;
+        row.get(usize::try_from(c).ok()?).copied()
+    };
+
+    for length in min_length.max(1)..=max_length {
+        for (r, row) in rows.iter().enumerate() {
+            for c in 0..row.len() {
+                // a single key is the same walk in every direction
+                let directions = if length == 1 {
+                    &DIRECTIONS[..1]
+                } else {
+                    &DIRECTIONS[..]
+                };
+                for &(dr, dc) in directions {
+                    let walk: Option<String> = (0..length as isize)
+                        .map(|i| key(r as isize + dr * i, c as isize + dc * i))
+                        .collect();
+                    if let Some(walk) = walk {
+                        if !f(walk)? {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(layout: Layout, min_length: usize, max_length: usize) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut writer = Emitter::new(&mut out);
+        generate_walks(&mut writer, layout, min_length, max_length).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn walks_go_both_ways_and_across_rows() {
+        let walks = generate(Layout::Qwerty, 3, 4);
+        for walk in ["qwe", "ewq", "1qaz", "zaq1", "asdf"] {
+            assert!(walks.iter().any(|w| w == walk), "missing {}", walk);
+        }
+        assert!(walks.iter().all(|w| (3..=4).contains(&w.chars().count())));
+    }
+
+    #[test]
+    fn count_matches_generated() {
+        for layout in [
+            Layout::Qwerty,
+            Layout::Qwertz,
+            Layout::Azerty,
+            Layout::Dvorak,
+        ] {
+            for (min, max) in [(1, 1), (1, 3), (3, 3), (2, 6), (0, 12)] {
+                let count = generate(layout, min, max).len() as u128;
+                assert_eq!(
+                    count_walks(layout, min, max),
+                    count,
+                    "{:?} {}-{}",
+                    layout,
+                    min,
+                    max
+                );
+            }
+        }
+    }
+}
@@ -1,19 +1,66 @@
+mod bench;
+mod config;
+mod dictionary;
+mod emit;
 mod generate;
+mod hash;
+mod keyboard;
+mod mangle;
+mod markov;
+mod merge;
+mod profile;
+mod rng;
+mod signal;
+mod stats;
 
-use clap::Parser;
-use generate::{generate_wordlist, ReplacePair};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use dictionary::{read_dictionary, Charset, Dictionary, Format, ReadOptions};
+use emit::{Emitter, Encoding, Order};
+use generate::{
+    estimate_permutation_count, generate_wordlist, Anchor, CapitalizeAfter, JoinStyle, LeetPreset,
+    LeetTarget, NumericRange, Options, ReplacePair, Transliteration,
+};
+use hash::HashAlgo;
+use keyboard::Layout;
+use markov::Markov;
+use profile::Profile;
 use std::error::Error;
-use std::fs::{File, OpenOptions};
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, BufRead, BufReader, Seek};
 use std::path::PathBuf;
+use std::process::{Child, Stdio};
+use std::time::{Duration, Instant};
+
+// progress and summary lines, sent to stderr when the words themselves go to stdout
+macro_rules! status {
+    ($stderr:expr, $($arg:tt)*) => {
+        if $stderr {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
 
 /// Simple program to generate a wordlist
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Output file to write to
-    #[arg(short, long, value_hint = clap::ValueHint::DirPath)]
-    output: PathBuf,
+    #[arg(short, long, required_unless_present_any = ["summary_only", "count_only", "crack",
+          "split_by_length", "preview_only"],
+          value_hint = clap::ValueHint::DirPath)]
+    output: Option<PathBuf>,
+
+    /// Write each word to DIR/wordlist-<len>.txt by its length instead of one output file
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "pipe", "no_trailing_newline"],
+          value_hint = clap::ValueHint::DirPath)]
+    split_by_length: Option<PathBuf>,
 
     /// Input wordlist to permutate
     #[arg(short, long, value_hint = clap::ValueHint::DirPath)]
@@ -23,77 +70,976 @@ struct Args {
     #[arg(short, long, value_delimiter = ',')]
     terms: Vec<String>,
 
-    /// Characters to replace (i.e. e=3 to replace e's with 3's)
-    #[arg(short, long, value_parser=parse_key_val, default_value="o=0,e=3,l=1,i=!,a=@,s=$,t=7", value_delimiter=',')]
+    /// Characters to replace (i.e. e=3 to replace e's with 3's, ^a=@ or s$=z for only the first or last character)
+    /// (overrides --leet-preset)
+    #[arg(short, long, value_parser=parse_key_val, value_delimiter=',')]
     replacements: Vec<ReplacePair>,
 
-    /// Min/max password length
+    /// A set of replacements in --replacements syntax generating its own family of
+    /// variants, repeat for independent sets instead of one combined branching
+    #[arg(long, value_name = "RULES", value_parser = parse_rule_set,
+          conflicts_with_all = ["replacements", "leet_preset"])]
+    replacements_set: Vec<RuleSet>,
+
+    /// Only apply replacements to these characters
+    #[arg(long, value_enum, default_value_t = LeetTarget::All)]
+    leet_target: LeetTarget,
+
+    /// Keep the casing of input words (i.e. iPhone) as the base form, casing variants
+    /// flip letters from it instead of uppercasing a lowercased word
+    #[arg(long)]
+    preserve_base_case: bool,
+
+    /// --preserve-base-case for terms on their own, so i.e. ACME is always written as given
+    #[arg(long)]
+    terms_preserve_case: bool,
+
+    /// Most replacements applied to one word, i.e. 1 never leets two characters at once
+    #[arg(long, value_name = "K")]
+    max_leet_subs: Option<usize>,
+
+    /// Only capitalize the first letter and the first letter after each separator or
+    /// digit, instead of trying every casing
+    #[arg(long, value_enum, value_name = "BOUNDARY")]
+    capitalize_after: Option<CapitalizeAfter>,
+
+    /// Built-in set of replacements used when --replacements isn't given
+    #[arg(long, value_enum, default_value_t = LeetPreset::Common)]
+    leet_preset: LeetPreset,
+
+    /// Translate characters in every dictionary word and term before generating
+    /// (i.e. 0:o turns "passw0rd" into "password", 01:ol maps 0 to o and 1 to l)
+    #[arg(long, value_name = "FROM:TO", value_parser = parse_tr)]
+    tr: Vec<Transliteration>,
+
+    /// Also generate the zero-padded numbers START to END (i.e. 1:9999:4 for 0001 to 9999)
+    #[arg(long, value_name = "START:END:WIDTH", value_parser = parse_numeric_range,
+          conflicts_with_all = ["markov", "keyboard_walks"])]
+    numeric_range: Option<NumericRange>,
+
+    /// With --numeric-range, append the numbers to every dictionary word and term
+    /// instead of generating them alone
+    #[arg(long, requires = "numeric_range")]
+    as_suffix: bool,
+
+    /// Only generate words of exactly N bytes, in place of --length
+    #[arg(long, value_name = "N", conflicts_with = "length")]
+    exact_length: Option<usize>,
+
+    /// Also use every dictionary word and term with each year appended (i.e. 1990:2025)
+    #[arg(long, value_name = "START:END", value_parser = parse_numeric_range)]
+    suffix_years: Option<NumericRange>,
+
+    /// Also use every dictionary word and term with each year prepended
+    #[arg(long, value_name = "START:END", value_parser = parse_numeric_range)]
+    prefix_years: Option<NumericRange>,
+
+    /// Min/max password length, a min of 0 lets an empty term (-t "") through as the
+    /// empty string
     #[arg(short, long, num_args = 2, default_values_t = vec![3, 6])]
     length: Vec<u8>,
+
+    /// Layout of the dictionary file
+    #[arg(long, value_enum, default_value_t = Format::Lines)]
+    dictionary_format: Format,
+
+    /// Character encoding of the dictionary file
+    #[arg(long, value_enum, value_name = "ENC", default_value_t = Charset::Utf8)]
+    dictionary_encoding: Charset,
+
+    /// Column of a csv or tsv dictionary to take words from, starting at 1
+    #[arg(long, value_name = "N", default_value_t = 1,
+          value_parser = clap::value_parser!(u64).range(1..))]
+    dictionary_column: u64,
+
+    /// Remove these characters from every dictionary word (i.e. --strip-chars "\"',")
+    #[arg(long, value_name = "CHARS")]
+    strip_chars: Option<String>,
+
+    /// Drop repeated dictionary words before generating, keeping the first
+    #[arg(long)]
+    dictionary_dedup: bool,
+
+    /// Most threads used to read the dictionary, defaults to every core
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    threads: Option<u64>,
+
+    /// Add the first-letter acronym of multi-word dictionary lines (i.e. "my secret place" => "msp")
+    #[arg(long)]
+    acronym: bool,
+
+    /// Split dictionary lines on whitespace into separate words
+    #[arg(long)]
+    split_dictionary_words: bool,
+
+    /// With --split-dictionary-words, also keep the words of a line joined together
+    #[arg(long, requires = "split_dictionary_words")]
+    keep_joined: bool,
+
+    /// Don't treat an empty result as an error
+    #[arg(long)]
+    allow_empty: bool,
+
+    /// Print the first N generated words to stderr
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    preview: usize,
+
+    /// Stop after printing the preview, without writing the output file
+    #[arg(long, requires = "preview")]
+    preview_only: bool,
+
+    /// Also generate base words with a character doubled (i.e. "pasword" => "password")
+    #[arg(long)]
+    double_chars: bool,
+
+    /// Longest dictionary word or term that can be part of a concatenation
+    #[arg(long, value_name = "N")]
+    max_part_length: Option<usize>,
+
+    /// Longest concatenated word, defaults to the max length
+    #[arg(long, value_name = "N")]
+    max_concat_length: Option<usize>,
+
+    /// Only concatenate pairs, not chains of three or more
+    #[arg(long)]
+    no_recurse: bool,
+
+    /// Instead of concatenating every pair, only join each word with itself (catcat)
+    #[arg(long)]
+    doubled: bool,
+
+    /// Only concatenate terms after dictionary words, by default both orders are made
+    /// (admin + root and root + admin)
+    #[arg(long)]
+    dictionary_first: bool,
+
+    /// Never use the same word or term twice in one concatenation (no "catcat")
+    #[arg(long)]
+    no_repeat_terms: bool,
+
+    /// Only generate words containing this (can be repeated)
+    #[arg(long, value_name = "SUBSTR")]
+    must_contain: Vec<String>,
+
+    /// Drop words with fewer than K distinct characters
+    #[arg(long, value_name = "K", default_value_t = 0)]
+    min_unique_chars: usize,
+
+    /// Most kinds of mutation (casing, leet, concatenation, doubling) stacked on one word,
+    /// i.e. 1 gives cased or leeted words but never both
+    #[arg(long, value_name = "N")]
+    max_mutations: Option<usize>,
+
+    /// How concatenated words are joined
+    #[arg(long, value_enum, default_value_t = JoinStyle::Plain)]
+    join_style: JoinStyle,
+
+    /// Write `hash:word` lines using this digest
+    #[arg(long, value_enum)]
+    hash: Option<HashAlgo>,
+
+    /// Only write the digest, without the plaintext
+    #[arg(long, requires = "hash")]
+    hash_only: bool,
+
+    /// Hash every candidate and stop at the first whose digest is HASH, printing it.
+    /// The words up to the match are still written when --output is given
+    #[arg(long, value_name = "HASH", requires = "crack_algo")]
+    crack: Option<String>,
+
+    /// Digest --crack compares against
+    #[arg(long, value_enum, value_name = "ALGO", requires = "crack")]
+    crack_algo: Option<HashAlgo>,
+
+    /// Stop generating after this long (i.e. 30s, 5m, 1h)
+    #[arg(long, value_parser = parse_duration)]
+    max_runtime: Option<Duration>,
+
+    /// Stop after writing this many words
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+
+    /// Discard the first N generated words, i.e. to continue from where --limit stopped
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    skip: usize,
+
+    /// Generate the most probable words from an order-N character model of the dictionary
+    /// instead of permutating it
+    #[arg(long, value_name = "ORDER", requires = "dictionary")]
+    markov: Option<usize>,
+
+    /// Apply each line of a hashcat style rule file (i.e. `c $1`, `sa@ r`) to every
+    /// dictionary word and term instead of permutating them
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath,
+          conflicts_with_all = ["markov", "keyboard_walks"])]
+    mangle_rules: Option<PathBuf>,
+
+    /// Randomize the order of the output
+    #[arg(long)]
+    shuffle: bool,
+
+    /// Seed for --shuffle, the same seed gives the same order
+    #[arg(long, requires = "shuffle")]
+    seed: Option<u64>,
+
+    /// Order the words are written in, length orders hold every word in temporary files
+    /// until generation is done
+    #[arg(long, value_enum, default_value_t = Order::Natural, conflicts_with = "shuffle")]
+    order: Order,
+
+    /// Generate straight walks across the keyboard (i.e. qwerty, 1qaz) instead of
+    /// permutating words
+    #[arg(long, conflicts_with = "markov")]
+    keyboard_walks: bool,
+
+    /// Layout used by --keyboard-walks
+    #[arg(long, value_enum, default_value_t = Layout::Qwerty)]
+    keyboard_layout: Layout,
+
+    /// Leave out the dictionary words and terms themselves, only writing variants
+    #[arg(long)]
+    mutations_only: bool,
+
+    /// Skip words that were already written with different casing
+    #[arg(long)]
+    unique_ci: bool,
+
+    /// Like --unique-ci but comparing Unicode case folds, so i.e. straße and STRASSE
+    /// count as the same word
+    #[arg(long, conflicts_with = "unique_ci")]
+    unique_fold: bool,
+
+    /// Also write the words to stdout, progress messages then go to stderr
+    #[arg(long)]
+    stdout: bool,
+
+    /// Shell command the generated words are piped through, its output is what gets
+    /// written to --output (i.e. --pipe rev)
+    #[arg(long, value_name = "COMMAND")]
+    pipe: Option<String>,
+
+    /// Write words as $HEX[...], for tools that choke on raw control or non-ASCII bytes
+    #[arg(long, value_enum)]
+    encode: Option<Encoding>,
+
+    /// Flush the output every N words, for watching it live through --pipe or tail -f
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    flush_every: Option<u64>,
+
+    /// Don't end the file with a newline after the last word
+    #[arg(long)]
+    no_trailing_newline: bool,
+
+    /// Write a JSON line per generated word recording which rules produced it
+    #[arg(long, value_name = "PATH", value_hint = clap::ValueHint::FilePath)]
+    audit: Option<PathBuf>,
+
+    /// Print how long each phase of generation took to stderr
+    #[arg(long)]
+    profile: bool,
+
+    /// Print how many words would be generated and exit
+    #[arg(long)]
+    count_only: bool,
+
+    /// Like --count-only but print just the number, with no other output or warnings
+    #[arg(long, conflicts_with = "count_only")]
+    summary_only: bool,
+
+    /// TOML file with default values for any of these options, keyed by long name
+    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    config: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Analyze an existing wordlist
+    Stats {
+        /// Wordlist to analyze
+        #[arg(value_hint = clap::ValueHint::FilePath)]
+        wordlist: PathBuf,
+    },
+    /// Combine wordlists into one sorted file without duplicates
+    Merge {
+        /// File to write the merged list to
+        #[arg(short, long, value_hint = clap::ValueHint::FilePath)]
+        output: PathBuf,
+
+        /// Wordlists to merge
+        #[arg(required = true, value_hint = clap::ValueHint::FilePath)]
+        wordlists: Vec<PathBuf>,
+    },
+    /// Generate a fixed wordlist without writing it and report the throughput
+    Bench,
 }
 
-fn main() -> io::Result<()> {
-    let args = Args::parse();
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> io::Result<()> {
+    let args = parse_args(std::env::args_os().collect())?;
     // println!("{:#?}", args);
 
+    match args.command {
+        Some(Command::Stats { wordlist }) => {
+            let words = read_dictionary(wordlist, &ReadOptions::default())?.words;
+            stats::print_stats(&words);
+            return Ok(());
+        }
+        Some(Command::Merge { output, wordlists }) => {
+            let count = merge::merge_wordlists(&wordlists, &output)?;
+            println!("{} words merged!", count);
+            return Ok(());
+        }
+        Some(Command::Bench) => return bench::run(),
+        None => {}
+    }
+
     // parse args
-    let min_length = args.length[0] as usize;
-    let max_length = args.length[1] as usize;
-    let transforms = &args.replacements;
-    let terms: Vec<String> = args
-        .terms
+    let (min_length, max_length) = match args.exact_length {
+        Some(len) => (len, len),
+        None => (args.length[0] as usize, args.length[1] as usize),
+    };
+    let transforms = if args.replacements.is_empty() {
+        args.leet_preset.replacements()
+    } else {
+        args.replacements
+    };
+    // rules the target rules out are just dropped, every stage then agrees on them
+    let target = args.leet_target;
+    let transforms: Vec<ReplacePair> = transforms
         .into_iter()
-        .filter(|word| word.len() <= max_length)
+        .filter(|r| target.allows(r.from()))
         .collect();
-    let dictionary: Vec<String> = if let Some(dict_fname) = args.dictionary {
-        read_dictionary(dict_fname, max_length)?
+    let opts = Options {
+        transforms,
+        min_length,
+        max_length,
+        double_chars: args.double_chars,
+        max_part_length: args.max_part_length,
+        max_concat_length: args.max_concat_length,
+        no_recurse: args.no_recurse,
+        no_repeat_terms: args.no_repeat_terms,
+        dictionary_first: args.dictionary_first,
+        doubled: args.doubled,
+        join_style: args.join_style,
+        must_contain: args.must_contain,
+        max_mutations: args.max_mutations,
+        capitalize_after: args.capitalize_after,
+        replaced_only: false,
+        max_leet_subs: args.max_leet_subs,
+        preserve_base_case: args.preserve_base_case,
+        terms_preserve_case: args.terms_preserve_case,
+        echo: !args.stdout,
+    };
+    // later sets leave out the unchanged words the first set already wrote
+    let passes: Vec<Options> = if args.replacements_set.is_empty() {
+        vec![opts.clone()]
     } else {
-        Vec::new()
+        let sets = args.replacements_set.into_iter().enumerate();
+        sets.map(|(i, set)| Options {
+            transforms: set
+                .0
+                .into_iter()
+                .filter(|r| target.allows(r.from()))
+                .collect(),
+            replaced_only: i > 0,
+            ..opts.clone()
+        })
+        .collect()
     };
+    // translated first, the length that counts is the one generated from
+    let (mut terms, long_terms): (Vec<String>, Vec<String>) = args
+        .terms
+        .into_iter()
+        .map(|term| args.tr.iter().fold(term, |term, tr| tr.apply(&term)))
+        .partition(|word| word.len() <= max_length);
+    let mut profile = args.profile.then(Profile::default);
+    let start = Instant::now();
+    let dictionary = if let Some(dict_fname) = args.dictionary {
+        let read_opts = ReadOptions {
+            max_length,
+            format: args.dictionary_format,
+            charset: args.dictionary_encoding,
+            column: args.dictionary_column as usize,
+            acronym: args.acronym,
+            split_words: args.split_dictionary_words,
+            keep_joined: args.keep_joined,
+            strip_chars: args.strip_chars.unwrap_or_default(),
+            tr: args.tr,
+            dedup: args.dictionary_dedup,
+            threads: args.threads.map(|n| n as usize),
+        };
+        read_dictionary(dict_fname, &read_opts)?
+    } else {
+        Dictionary::default()
+    };
+    if let Some(profile) = profile.as_mut() {
+        profile.record("dictionary", start);
+    }
+    let quiet = args.summary_only;
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+    if !long_terms.is_empty() && !quiet {
+        let count = long_terms.len();
+        eprintln!(
+            "warning: {} term{} exceeded max length {} and {} ignored: {}",
+            count,
+            plural(count),
+            max_length,
+            if count == 1 { "was" } else { "were" },
+            long_terms.join(", ")
+        );
+    }
+    if dictionary.too_long > 0 && !quiet {
+        let count = dictionary.too_long;
+        eprintln!(
+            "warning: {} dictionary word{} exceeded max length {} and {} ignored",
+            count,
+            plural(count),
+            max_length,
+            if count == 1 { "was" } else { "were" }
+        );
+    }
+    let mut dictionary = dictionary.words;
+
+    let base_words: Vec<String> = match args.mutations_only {
+        true => dictionary.iter().chain(&terms).cloned().collect(),
+        false => Vec::new(),
+    };
+
+    // numbers added to either end of every base word, alongside the plain word.
+    // standalone --numeric-range numbers are emitted separately
+    let numbers = args.numeric_range.filter(|_| !args.as_suffix);
+    let mut affixes = Vec::new();
+    if let Some(range) = args.numeric_range.filter(|_| args.as_suffix) {
+        affixes.push((range, false));
+    }
+    if let Some(years) = args.suffix_years {
+        affixes.push((years, false));
+    }
+    if let Some(years) = args.prefix_years {
+        affixes.push((years, true));
+    }
+    if !affixes.is_empty() {
+        for words in [&mut dictionary, &mut terms] {
+            let base = words.clone();
+            for &(range, prefix) in &affixes {
+                words.extend(with_numbers(&base, range, prefix, max_length));
+            }
+        }
+    }
+
+    let mangle_rules = args
+        .mangle_rules
+        .as_deref()
+        .map(mangle::read_rules)
+        .transpose()?;
+    let estimate = || {
+        if let Some(order) = args.markov {
+            return Markov::train(&dictionary, order).count(min_length, max_length);
+        }
+        if args.keyboard_walks {
+            return keyboard::count_walks(args.keyboard_layout, min_length, max_length);
+        }
+        let count = match &mangle_rules {
+            Some(rules) => {
+                let words = [&dictionary[..], &terms[..]].concat();
+                mangle::count_mangled(&words, rules, min_length, max_length)
+            }
+            None => passes
+                .iter()
+                .map(|pass| estimate_permutation_count(&dictionary, &terms, pass))
+                .fold(0, u128::saturating_add),
+        };
+        numbers.map_or(count, |range| count.saturating_add(range.count(&opts)))
+    };
+    if quiet {
+        println!("{}", estimate());
+        return Ok(());
+    }
 
     // Print info
-    println!("Dictionary length: {}", dictionary.len());
-    println!("Terms length: {}", terms.len());
-    println!("Replacements: {}", transforms.len());
-    println!("Word min/max: {} - {}", min_length, max_length);
-
-    // open for read/write/create
-    let mut fout: File = OpenOptions::new()
-        .create(true)
-        .read(true)
-        .write(true)
-        .truncate(true)
-        .open(args.output)?;
+    let to_stderr = args.stdout;
+    status!(to_stderr, "Dictionary length: {}", dictionary.len());
+    status!(to_stderr, "Terms length: {}", terms.len());
+    let rules: usize = passes.iter().map(|pass| pass.transforms.len()).sum();
+    status!(to_stderr, "Replacements: {}", rules);
+    status!(to_stderr, "Word min/max: {} - {}", min_length, max_length);
+
+    if args.count_only {
+        println!("{} words would be generated", estimate());
+        return Ok(());
+    }
+
+    // open for read/write/create, nothing is written for a preview or a --crack run
+    // without --output. fifos and devices can't be truncated or read back, so they're
+    // opened write-only
+    let output = args.output.filter(|_| !args.preview_only);
+    let stream = output
+        .as_ref()
+        .is_some_and(|output| fs::metadata(output).is_ok_and(|meta| !meta.file_type().is_file()));
+    let fout: Option<File> = match &output {
+        None => None,
+        Some(output) if stream => Some(OpenOptions::new().write(true).open(output)?),
+        Some(output) => {
+            let fout = OpenOptions::new()
+                .create(true)
+                .read(true)
+                .write(true)
+                .truncate(true)
+                .open(output)?;
+            Some(fout)
+        }
+    };
 
     // generate 'em
-    println!("Generating...");
-    generate_wordlist(&fout, dictionary, terms, transforms, min_length, max_length)?;
+    status!(to_stderr, "Generating...");
+    signal::install();
+    let mut child = match &args.pipe {
+        Some(command) => Some(spawn_pipe(command, fout.as_ref())?),
+        None => None,
+    };
+    let stdin = child.as_mut().and_then(|child| child.stdin.take());
+    let mut writer = match (stdin, &fout) {
+        (Some(stdin), _) => Emitter::new(stdin),
+        (None, Some(fout)) => Emitter::new(fout),
+        (None, None) => Emitter::new(io::sink()),
+    };
+    writer.preview = args.preview;
+    writer.preview_only = args.preview_only;
+    writer.hash = args.hash;
+    writer.hash_only = args.hash_only;
+    writer.encoding = args.encode;
+    // a runtime too long to represent is no deadline at all
+    writer.deadline = args
+        .max_runtime
+        .and_then(|runtime| Instant::now().checked_add(runtime));
+    writer.limit = args.limit;
+    writer.skip = args.skip;
+    writer.min_unique_chars = args.min_unique_chars;
+    writer.exact_length = args.exact_length;
+    writer.flush_every = args.flush_every.map(|n| n as usize);
+    writer.trailing_newline = !args.no_trailing_newline;
+    writer.crack = args
+        .crack
+        .as_ref()
+        .zip(args.crack_algo)
+        .map(|(hash, algo)| (algo, hash.to_lowercase()));
+    writer.profile = profile.take();
+    if args.shuffle {
+        let seed = args.seed.unwrap_or_else(|| {
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+            now.map_or(0, |d| d.as_nanos() as u64)
+        });
+        writer.shuffle(seed);
+    }
+    writer.order(args.order)?;
+    let split_by_length = args.split_by_length.filter(|_| !args.preview_only);
+    if let Some(dir) = &split_by_length {
+        writer.split_by_length(dir.clone())?;
+    }
+    if args.stdout {
+        writer.tee(io::stdout());
+    }
+    writer.exclude(base_words);
+    if args.unique_ci {
+        writer.unique_ci();
+    }
+    if args.unique_fold {
+        writer.unique_fold();
+    }
+    if let Some(path) = &args.audit {
+        writer.audit(File::create(path)?);
+    }
+    if let Some(order) = args.markov {
+        let start = writer.start();
+        Markov::train(&dictionary, order).generate(&mut writer, min_length, max_length)?;
+        writer.flush()?;
+        writer.record("markov", start);
+    } else if args.keyboard_walks {
+        let start = writer.start();
+        keyboard::generate_walks(&mut writer, args.keyboard_layout, min_length, max_length)?;
+        writer.flush()?;
+        writer.record("keyboard walks", start);
+    } else if let Some(rules) = &mangle_rules {
+        let start = writer.start();
+        for words in [&dictionary, &terms] {
+            mangle::generate_mangled(&mut writer, words, rules, min_length, max_length)?;
+        }
+        writer.flush()?;
+        writer.record("mangling", start);
+        if let Some(range) = numbers {
+            range.generate(&mut writer, &opts)?;
+        }
+    } else {
+        for pass in &passes {
+            generate_wordlist(&mut writer, &dictionary, &terms, pass)?;
+        }
+        if let Some(range) = numbers {
+            range.generate(&mut writer, &opts)?;
+        }
+    }
+    writer.finish()?;
+    if writer.timed_out() {
+        status!(to_stderr, "Max runtime reached, stopped early");
+    }
+    if signal::interrupted() {
+        status!(to_stderr, "Interrupted, stopped early");
+    }
+    if writer.closed() {
+        status!(to_stderr, "Output closed, stopped early");
+    }
+    if let Some(hash) = &args.crack {
+        match writer.cracked() {
+            Some(word) => status!(to_stderr, "Cracked {}: {}", hash, word),
+            None => status!(to_stderr, "No candidate matched {}", hash),
+        }
+    }
+    let mut profile = writer.profile.take();
+    let written = writer.count();
+    drop(writer);
+    if let Some(mut child) = child {
+        let status = child.wait()?;
+        if !status.success() {
+            eprintln!("warning: --pipe command exited with {}", status);
+        }
+    }
 
-    // count resulting lines
-    fout.seek(std::io::SeekFrom::Start(0))?;
-    let line_count = BufReader::new(&fout).lines().count();
-    println!("{} words generated!", line_count);
+    // count resulting lines, what went into a stream or split files is all there is to go on
+    let start = Instant::now();
+    let line_count = match fout {
+        Some(mut fout) if !stream => {
+            fout.seek(std::io::SeekFrom::Start(0))?;
+            BufReader::new(&fout).lines().count()
+        }
+        Some(_) => written,
+        None if split_by_length.is_some() => written,
+        None => {
+            if let Some(profile) = profile {
+                profile.print();
+            }
+            return Ok(());
+        }
+    };
+    status!(to_stderr, "{} words generated!", line_count);
+    if let Some(mut profile) = profile.take() {
+        profile.record("counting", start);
+        profile.print();
+    }
+
+    // an interrupt before the first word is still an interrupt, not an empty result
+    if signal::interrupted() {
+        std::process::exit(130);
+    }
+    if line_count == 0 && !args.allow_empty {
+        eprintln!("warning: no words were generated, check the length range and inputs (use --allow-empty to permit this)");
+        std::process::exit(1);
+    }
 
     Ok(())
 }
 
-fn read_dictionary(filename: PathBuf, max_length: usize) -> io::Result<Vec<String>> {
-    let file = File::open(filename)?;
-    let lines = io::BufReader::new(file).lines();
+// `words` with each number of `range` added to the start or end, as long as they fit
+fn with_numbers(
+    words: &[String],
+    range: NumericRange,
+    prefix: bool,
+    max_length: usize,
+) -> Vec<String> {
+    words
+        .iter()
+        .flat_map(|word| {
+            range.numbers().map(move |n| match prefix {
+                true => format!("{n}{word}"),
+                false => format!("{word}{n}"),
+            })
+        })
+        .filter(|word| word.len() <= max_length)
+        .collect()
+}
+
+/// Start `command` in a shell, reading words on stdin and writing to `out`
+fn spawn_pipe(command: &str, out: Option<&File>) -> io::Result<Child> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = std::process::Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = std::process::Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let stdout = match out {
+        Some(out) => Stdio::from(out.try_clone()?),
+        None => Stdio::null(),
+    };
+    shell
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(stdout)
+        .spawn()
+}
 
-    let dictionary: Vec<String> = lines
-        .filter_map(|line| line.ok())
-        .map(|word| word.trim().to_string())
-        .filter(|word| word.len() > 0 && word.len() <= max_length)
-        .collect();
+/// Parse the command line, filling in anything left unset from `--config`
+fn parse_args(mut argv: Vec<OsString>) -> io::Result<Args> {
+    let cmd = Args::command();
+    // required options may come from the config, they're checked once it's read
+    let matches = cmd.clone().ignore_errors(true).get_matches_from(&argv);
 
-    Ok(dictionary)
+    if let Some(path) = matches.get_one::<PathBuf>("config") {
+        argv.extend(config::config_args(path, &cmd, &matches)?);
+    }
+    let matches = cmd.get_matches_from(argv);
+    Ok(Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()))
 }
 
-/// Parse a single key-value pair
+/// Parse a single key-value pair, the key may be anchored with `^` or `$`
 fn parse_key_val(s: &str) -> Result<ReplacePair, Box<dyn Error + Send + Sync + 'static>> {
     let pos = s
         .find('=')
         .ok_or_else(|| format!("invalid KEY=value: no `=` found in `{s}`"))?;
-    Ok(ReplacePair(s[..pos].parse()?, s[pos + 1..].parse()?))
+    let key = &s[..pos];
+    let (key, anchor) = if let Some(key) = key.strip_prefix('^').filter(|k| !k.is_empty()) {
+        (key, Anchor::Start)
+    } else if let Some(key) = key.strip_suffix('$').filter(|k| !k.is_empty()) {
+        (key, Anchor::End)
+    } else {
+        (key, Anchor::Anywhere)
+    };
+    Ok(ReplacePair(key.parse()?, s[pos + 1..].parse()?, anchor))
+}
+
+/// One `--replacements-set`
+#[derive(Debug, Clone)]
+struct RuleSet(Vec<ReplacePair>);
+
+fn parse_rule_set(s: &str) -> Result<RuleSet, Box<dyn Error + Send + Sync + 'static>> {
+    let rules = s.split(',').map(parse_key_val).collect::<Result<_, _>>()?;
+    Ok(RuleSet(rules))
+}
+
+/// Parse a `tr` style `FROM:TO` pair of equal length character sets
+fn parse_tr(s: &str) -> Result<Transliteration, Box<dyn Error + Send + Sync + 'static>> {
+    let (from, to) = s
+        .split_once(':')
+        .ok_or_else(|| format!("invalid FROM:TO: no `:` found in `{s}`"))?;
+    if from.chars().count() != to.chars().count() {
+        return Err(format!("`{from}` and `{to}` must have the same number of characters").into());
+    }
+    Ok(Transliteration(from.chars().zip(to.chars()).collect()))
+}
+
+/// Parse a `START:END:WIDTH` number range, WIDTH may be left off for no padding
+fn parse_numeric_range(s: &str) -> Result<NumericRange, Box<dyn Error + Send + Sync + 'static>> {
+    let mut fields = s.split(':');
+    let (Some(start), Some(end), width, None) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return Err(format!("invalid START:END:WIDTH `{s}`").into());
+    };
+    let range = NumericRange {
+        start: start.parse()?,
+        end: end.parse()?,
+        width: width.map_or(Ok(0), str::parse)?,
+    };
+    if range.start > range.end {
+        return Err(format!("start {} is after end {}", range.start, range.end).into());
+    }
+    Ok(range)
+}
+
+/// Parse a duration like `500ms`, `30s`, `5m` or `1h` (plain numbers are seconds)
+fn parse_duration(s: &str) -> Result<Duration, Box<dyn Error + Send + Sync + 'static>> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let num: u64 = num.parse()?;
+    let secs = |per: u64| {
+        num.checked_mul(per)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("duration `{s}` is too long"))
+    };
+    let duration = match unit {
+        "ms" => Duration::from_millis(num),
+        "" | "s" => Duration::from_secs(num),
+        "m" => secs(60)?,
+        "h" => secs(60 * 60)?,
+        _ => return Err(format!("invalid duration unit `{unit}`, expected ms, s, m or h").into()),
+    };
+    Ok(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(argv: &[&str]) -> Args {
+        let argv = std::iter::once("wordlirst").chain(argv.iter().copied());
+        parse_args(argv.map(OsString::from).collect()).unwrap()
+    }
+
+    // everything but where the settings came from
+    fn settings(mut args: Args) -> String {
+        args.config = None;
+        format!("{:?}", args)
+    }
+
+    fn config_file(name: &str, text: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("wordlirst-{}-{}.toml", name, std::process::id()));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn config_matches_the_same_flags() {
+        let path = config_file(
+            "same",
+            r#"
+            # every kind of value
+            output = "out.txt"
+            dictionary = 'words.txt'
+            terms = ["acme", "corp"]
+            length = [3, 12]
+            replacements = ["a=@", "^s=$", "o$=0"]
+            max-part-length = 6
+            max_mutations = 2
+            no_recurse = true
+            dictionary_first = false
+            join-style = "snake"
+            capitalize_after = "separator"
+            must_contain = ["ac"]
+            suffix_years = "2020:2024:4"
+            max_runtime = "10s"
+            "#,
+        );
+        let from_config = parse(&["--config", path.to_str().unwrap()]);
+        fs::remove_file(&path).unwrap();
+        let from_flags = parse(&[
+            "-o",
+            "out.txt",
+            "-d",
+            "words.txt",
+            "-t",
+            "acme,corp",
+            "-l",
+            "3",
+            "12",
+            "-r",
+            "a=@,^s=$,o$=0",
+            "--max-part-length",
+            "6",
+            "--max-mutations",
+            "2",
+            "--no-recurse",
+            "--join-style",
+            "snake",
+            "--capitalize-after",
+            "separator",
+            "--must-contain",
+            "ac",
+            "--suffix-years",
+            "2020:2024:4",
+            "--max-runtime",
+            "10s",
+        ]);
+        assert_eq!(settings(from_config), settings(from_flags));
+    }
+
+    #[test]
+    fn flags_override_the_config() {
+        let path = config_file(
+            "override",
+            "length = [3, 12]\nno_recurse = true\noutput = \"a.txt\"\n",
+        );
+        let args = parse(&[
+            "--config",
+            path.to_str().unwrap(),
+            "-l",
+            "1",
+            "4",
+            "-o",
+            "b.txt",
+        ]);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(args.length, [1, 4]);
+        assert_eq!(args.output, Some(PathBuf::from("b.txt")));
+        assert!(args.no_recurse);
+    }
+
+    #[test]
+    fn config_errors_name_the_file_and_key() {
+        let path = config_file("unknown", "lenght = [3, 12]\n");
+        let err = parse_args(
+            ["wordlirst", "--config", path.to_str().unwrap()]
+                .map(OsString::from)
+                .to_vec(),
+        )
+        .unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            format!("{}: unknown key `lenght`", path.display())
+        );
+    }
+
+    #[test]
+    fn numeric_ranges_pad_to_the_width() {
+        let range = parse_numeric_range("1:5:3").unwrap();
+        assert_eq!(
+            range.numbers().collect::<Vec<_>>(),
+            ["001", "002", "003", "004", "005"]
+        );
+        let range = parse_numeric_range("8:11").unwrap();
+        assert_eq!(range.numbers().collect::<Vec<_>>(), ["8", "9", "10", "11"]);
+        // wider numbers aren't cut to the width
+        let range = parse_numeric_range("99:100:2").unwrap();
+        assert_eq!(range.numbers().collect::<Vec<_>>(), ["99", "100"]);
+        assert!(parse_numeric_range("5:1").is_err());
+        assert!(parse_numeric_range("1:2:3:4").is_err());
+    }
+
+    #[test]
+    fn years_are_added_to_each_word() {
+        let years = parse_numeric_range("2023:2025").unwrap();
+        let words = vec!["acme".to_string(), "administrator".to_string()];
+        assert_eq!(
+            with_numbers(&words, years, false, 10),
+            ["acme2023", "acme2024", "acme2025"]
+        );
+        assert_eq!(
+            with_numbers(&words, years, true, 8),
+            ["2023acme", "2024acme", "2025acme"]
+        );
+        assert!(with_numbers(&words, years, false, 7).is_empty());
+    }
+
+    #[test]
+    fn default_replacements_are_the_library_default() {
+        let args = parse(&["-t", "admin", "-o", "out.txt"]);
+        assert!(args.replacements.is_empty());
+        let pairs: Vec<(char, char)> = args
+            .leet_preset
+            .replacements()
+            .iter()
+            .map(|r| (r.from(), r.to()))
+            .collect();
+        assert_eq!(pairs, generate::DEFAULT_REPLACEMENTS);
+    }
+
+    #[test]
+    fn durations_too_long_are_errors() {
+        assert_eq!(parse_duration("90m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        let err = parse_duration("999999999999999999h").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "duration `999999999999999999h` is too long"
+        );
+        assert!(parse_duration("5d").is_err());
+    }
 }
@@ -0,0 +1,260 @@
+use crate::emit::Emitter;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// One step of a mangling rule, a subset of hashcat's rule functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// `:` leave the word as is
+    Nothing,
+    /// `l`
+    Lowercase,
+    /// `u`
+    Uppercase,
+    /// `c` first letter upper, the rest lower
+    Capitalize,
+    /// `C` first letter lower, the rest upper
+    InvertCapitalize,
+    /// `t`
+    ToggleCase,
+    /// `r`
+    Reverse,
+    /// `d` word twice
+    Duplicate,
+    /// `f` word followed by its reverse
+    Reflect,
+    /// `$X`
+    Append(char),
+    /// `^X`
+    Prepend(char),
+    /// `sXY` every X becomes Y
+    Replace(char, char),
+    /// `[`
+    DeleteFirst,
+    /// `]`
+    DeleteLast,
+}
+
+/// A line of a `--mangle-rules` file, its steps applied left to right
+#[derive(Debug, Clone)]
+pub struct Rule(Vec<Op>);
+
+impl Rule {
+    pub fn apply(&self, word: &str) -> String {
+        let mut word = word.to_string();
+        for op in &self.0 {
+            word = match *op {
+                Op::Nothing => word,
+                Op::Lowercase => word.to_lowercase(),
+                Op::Uppercase => word.to_uppercase(),
+                Op::Capitalize => {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first
+                            .to_uppercase()
+                            .chain(chars.flat_map(char::to_lowercase))
+                            .collect(),
+                        None => word,
+                    }
+                }
+                Op::InvertCapitalize => {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first
+                            .to_lowercase()
+                            .chain(chars.flat_map(char::to_uppercase))
+                            .collect(),
+                        None => word,
+                    }
+                }
+                Op::ToggleCase => word.chars().flat_map(toggle).collect(),
+                Op::Reverse => word.chars().rev().collect(),
+                Op::Duplicate => word.repeat(2),
+                Op::Reflect => {
+                    let reversed: String = word.chars().rev().collect();
+                    word + &reversed
+                }
+                Op::Append(c) => {
+                    word.push(c);
+                    word
+                }
+                Op::Prepend(c) => {
+                    word.insert(0, c);
+                    word
+                }
+                Op::Replace(from, to) => word.replace(from, &to.to_string()),
+                Op::DeleteFirst => word.chars().skip(1).collect(),
+                Op::DeleteLast => {
+                    word.pop();
+                    word
+                }
+            };
+        }
+        word
+    }
+}
+
+fn toggle(c: char) -> Vec<char> {
+    if c.is_lowercase() {
+        c.to_uppercase().collect()
+    } else {
+        c.to_lowercase().collect()
+    }
+}
+
+/// Load rules, one per line. Blank lines and lines starting with `#` are skipped,
+/// spaces between steps are ignored.
+pub fn read_rules(path: &Path) -> io::Result<Vec<Rule>> {
+    let text = fs::read_to_string(path)?;
+    let mut rules = Vec::new();
+    for (num, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let rule = parse_rule(line).map_err(|msg| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: {}", path.display(), num + 1, msg),
+            )
+        })?;
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+fn parse_rule(line: &str) -> Result<Rule, String> {
+    let mut ops = Vec::new();
+    let mut chars = line.chars();
+    let arg = |chars: &mut std::str::Chars, op: char| {
+        chars
+            .next()
+            .ok_or_else(|| format!("`{op}` needs a character after it"))
+    };
+    while let Some(c) = chars.next() {
+        let op = match c {
+            ' ' | '\t' => continue,
+            ':' => Op::Nothing,
+            'l' => Op::Lowercase,
+            'u' => Op::Uppercase,
+            'c' => Op::Capitalize,
+            'C' => Op::InvertCapitalize,
+            't' => Op::ToggleCase,
+            'r' => Op::Reverse,
+            'd' => Op::Duplicate,
+            'f' => Op::Reflect,
+            '$' => Op::Append(arg(&mut chars, c)?),
+            '^' => Op::Prepend(arg(&mut chars, c)?),
+            's' => Op::Replace(arg(&mut chars, c)?, arg(&mut chars, c)?),
+            '[' => Op::DeleteFirst,
+            ']' => Op::DeleteLast,
+            c => return Err(format!("unknown rule `{c}`")),
+        };
+        ops.push(op);
+    }
+    Ok(Rule(ops))
+}
+
+/// Number of words `generate_mangled` would write
+pub fn count_mangled(
+    words: &[String],
+    rules: &[Rule],
+    min_length: usize,
+    max_length: usize,
+) -> u128 {
+    let lengths = words
+        .iter()
+        .flat_map(|word| rules.iter().map(|rule| rule.apply(word).len()));
+    lengths
+        .filter(|len| (min_length..=max_length).contains(len))
+        .count() as u128
+}
+
+/// Apply every rule to every word, writing results within the length range
+pub fn generate_mangled(
+    writer: &mut Emitter,
+    words: &[String],
+    rules: &[Rule],
+    min_length: usize,
+    max_length: usize,
+) -> io::Result<()> {
+    for word in words {
+        for rule in rules {
+            if writer.is_done() {
+                return Ok(());
+            }
+            let mangled = rule.apply(word);
+            if (min_length..=max_length).contains(&mangled.len()) {
+                writer.emit(&mangled)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(rule: &str, word: &str) -> String {
+        parse_rule(rule).unwrap().apply(word)
+    }
+
+    #[test]
+    fn rules_apply_left_to_right() {
+        assert_eq!(apply(":", "Pass"), "Pass");
+        assert_eq!(apply("l", "PaSS"), "pass");
+        assert_eq!(apply("u", "pass"), "PASS");
+        assert_eq!(apply("c", "pASS"), "Pass");
+        assert_eq!(apply("C", "pass"), "pASS");
+        assert_eq!(apply("t", "PaSs"), "pAsS");
+        assert_eq!(apply("r", "abc"), "cba");
+        assert_eq!(apply("d", "ab"), "abab");
+        assert_eq!(apply("f", "ab"), "abba");
+        assert_eq!(apply("$1 $2", "ab"), "ab12");
+        assert_eq!(apply("^1^2", "ab"), "21ab");
+        assert_eq!(apply("sa@", "banana"), "b@n@n@");
+        assert_eq!(apply("[ ]", "abcd"), "bc");
+        assert_eq!(apply("c $1 sa@", "admin"), "Admin1");
+        assert_eq!(apply("sa@ c", "admin"), "@dmin");
+        assert_eq!(apply("[", ""), "");
+    }
+
+    #[test]
+    fn bad_rules_are_rejected() {
+        assert!(parse_rule("$").is_err());
+        assert!(parse_rule("sa").is_err());
+        assert!(parse_rule("x").is_err());
+    }
+
+    #[test]
+    fn read_errors_name_the_line() {
+        let path = std::env::temp_dir().join(format!("wordlirst-rules-{}", std::process::id()));
+        fs::write(&path, "# comment\n\nc\n$").unwrap();
+        let err = read_rules(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err
+            .to_string()
+            .ends_with(":4: `$` needs a character after it"));
+    }
+
+    #[test]
+    fn count_matches_generated() {
+        let words = vec!["ab".to_string(), "hello".to_string()];
+        let rules: Vec<Rule> = [":", "d", "$1", "f", "[ ]"]
+            .iter()
+            .map(|rule| parse_rule(rule).unwrap())
+            .collect();
+        let mut out = Vec::new();
+        let mut writer = Emitter::new(&mut out);
+        generate_mangled(&mut writer, &words, &rules, 3, 6).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        let text = String::from_utf8(out).unwrap();
+        let written: Vec<&str> = text.lines().collect();
+        assert_eq!(written, ["abab", "ab1", "abba", "hello", "hello1", "ell"]);
+        assert_eq!(count_mangled(&words, &rules, 3, 6), written.len() as u128);
+    }
+}
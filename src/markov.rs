@@ -0,0 +1,229 @@
+use crate::emit::Emitter;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::io;
+
+/// Order-N character model trained on a word list
+pub struct Markov {
+    order: usize,
+    // context => (next char or None for end of word) => probability
+    model: HashMap<String, Vec<(Option<char>, f64)>>,
+}
+
+impl Markov {
+    pub fn train(words: &[String], order: usize) -> Self {
+        let mut counts: HashMap<String, HashMap<Option<char>, u32>> = HashMap::new();
+        for word in words {
+            let chars: Vec<char> = word.chars().collect();
+            for i in 0..=chars.len() {
+                let context = context(&chars[..i], order);
+                *counts
+                    .entry(context)
+                    .or_default()
+                    .entry(chars.get(i).copied())
+                    .or_insert(0) += 1;
+            }
+        }
+
+        let model = counts
+            .into_iter()
+            .map(|(context, next)| {
+                let total: u32 = next.values().sum();
+                let mut next: Vec<(Option<char>, f64)> = next
+                    .into_iter()
+                    .map(|(c, n)| (c, n as f64 / total as f64))
+                    .collect();
+                next.sort_by_key(|a| a.0);
+                (context, next)
+            })
+            .collect();
+        Markov { order, model }
+    }
+
+    /// Emit words between `min_length` and `max_length` chars, most probable first.
+    ///
+    /// Expands the partial word with the best probability so far, which can only go
+    /// down as it grows, so finished words come out in order. Use the emitter's limit
+    /// to cap the run, otherwise every word the model can produce is emitted.
+    pub fn generate(
+        &self,
+        writer: &mut Emitter,
+        min_length: usize,
+        max_length: usize,
+    ) -> io::Result<()> {
+        let mut queue = BinaryHeap::new();
+        queue.push(Candidate {
+            log_prob: 0.0,
+            word: Vec::new(),
+            done: false,
+        });
+
+        while let Some(candidate) = queue.pop() {
+            if writer.is_done() {
+                break;
+            }
+            if candidate.done {
+                writer.emit(&candidate.word.iter().collect::<String>())?;
+                continue;
+            }
+            let Some(next) = self.model.get(&context(&candidate.word, self.order)) else {
+                continue;
+            };
+            for &(c, prob) in next {
+                let log_prob = candidate.log_prob + prob.ln();
+                match c {
+                    None if candidate.word.len() >= min_length => queue.push(Candidate {
+                        log_prob,
+                        word: candidate.word.clone(),
+                        done: true,
+                    }),
+                    Some(c) if candidate.word.len() < max_length => {
+                        let mut word = candidate.word.clone();
+                        word.push(c);
+                        queue.push(Candidate {
+                            log_prob,
+                            word,
+                            done: false,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of words `generate` emits for the same lengths when run to the end
+    pub fn count(&self, min_length: usize, max_length: usize) -> u128 {
+        let mut memo = HashMap::new();
+        self.count_from(&[], min_length, max_length, &mut memo)
+    }
+
+    // words that can still be finished from the partial word `chars`, only its
+    // context and length matter
+    fn count_from(
+        &self,
+        chars: &[char],
+        min_length: usize,
+        max_length: usize,
+        memo: &mut HashMap<(String, usize), u128>,
+    ) -> u128 {
+        let context = context(chars, self.order);
+        let key = (context, chars.len());
+        if let Some(&count) = memo.get(&key) {
+            return count;
+        }
+        let mut count = 0u128;
+        for &(c, _) in self.model.get(&key.0).into_iter().flatten() {
+            match c {
+                None if chars.len() >= min_length => count += 1,
+                Some(c) if chars.len() < max_length => {
+                    let mut chars = chars.to_vec();
+                    chars.push(c);
+                    count =
+                        count.saturating_add(self.count_from(&chars, min_length, max_length, memo));
+                }
+                _ => {}
+            }
+        }
+        memo.insert(key, count);
+        count
+    }
+}
+
+// the last `order` chars, shorter contexts only happen at the start of a word
+fn context(chars: &[char], order: usize) -> String {
+    chars[chars.len().saturating_sub(order)..].iter().collect()
+}
+
+struct Candidate {
+    log_prob: f64,
+    word: Vec<char>,
+    done: bool,
+}
+
+// max-heap on probability, ties broken alphabetically so output is deterministic
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.log_prob
+            .total_cmp(&other.log_prob)
+            .then_with(|| other.word.cmp(&self.word))
+            .then_with(|| self.done.cmp(&other.done))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(words: &[&str], order: usize, min_length: usize, max_length: usize) -> Vec<String> {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        let mut out = Vec::new();
+        let mut writer = Emitter::new(&mut out);
+        Markov::train(&words, order)
+            .generate(&mut writer, min_length, max_length)
+            .unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn most_probable_first() {
+        assert_eq!(generate(&["abc", "abd", "abc"], 2, 1, 5), ["abc", "abd"]);
+    }
+
+    #[test]
+    fn count_matches_generated() {
+        let cases: &[(&[&str], usize, usize, usize)] = &[
+            (&["abc", "abd"], 1, 3, 6),
+            (&["abc", "abd"], 2, 1, 10),
+            (&["hello", "help", "yellow", "fellow"], 1, 2, 7),
+            (&["hello", "help", "yellow", "fellow"], 2, 4, 8),
+            (&["aa", "aba", "b"], 0, 1, 5),
+            (&["pass", "word", "password"], 3, 4, 12),
+        ];
+        for &(words, order, min, max) in cases {
+            let trained: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+            let count = Markov::train(&trained, order).count(min, max);
+            let generated = generate(words, order, min, max);
+            assert_eq!(
+                count,
+                generated.len() as u128,
+                "{:?} order {}",
+                words,
+                order
+            );
+        }
+    }
+
+    #[test]
+    fn likelier_words_come_first() {
+        // "ab" is 1/2 likely, "b" 1/3 and "ac" 1/6
+        let words = ["ab", "ab", "ab", "ac", "b", "b"];
+        assert_eq!(generate(&words, 1, 1, 5), ["ab", "b", "ac"]);
+        assert_eq!(generate(&words, 1, 2, 5), ["ab", "ac"]);
+
+        // "a" can follow itself, so shorter words still come before longer ones
+        let out = generate(&["aa", "a"], 1, 1, 4);
+        assert_eq!(out, ["a", "aa", "aaa", "aaaa"]);
+    }
+}
@@ -0,0 +1,151 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Lines held in memory before a sorted run is spilled to a temp file
+const RUN_LENGTH: usize = 1_000_000;
+
+/// Write the sorted, deduplicated union of `inputs` to `output`, returning the number of
+/// lines written.
+///
+/// Inputs are read in runs of `RUN_LENGTH` lines which are sorted, deduped and spilled
+/// to temp files, then merged, so memory use doesn't grow with the size of the inputs.
+pub fn merge_wordlists(inputs: &[PathBuf], output: &Path) -> io::Result<usize> {
+    let mut runs = Vec::new();
+    let mut lines: Vec<Vec<u8>> = Vec::new();
+    for input in inputs {
+        for line in BufReader::new(File::open(input)?).split(b'\n') {
+            let mut line = line?;
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            if line.is_empty() {
+                continue;
+            }
+            lines.push(line);
+            if lines.len() >= RUN_LENGTH {
+                runs.push(spill(&mut lines, runs.len())?);
+            }
+        }
+    }
+
+    let mut writer = BufWriter::new(File::create(output)?);
+    let count = if runs.is_empty() {
+        // everything fit in memory
+        sort_dedup(&mut lines);
+        for line in &lines {
+            writer.write_all(line)?;
+            writer.write_all(b"\n")?;
+        }
+        lines.len()
+    } else {
+        if !lines.is_empty() {
+            runs.push(spill(&mut lines, runs.len())?);
+        }
+        let count = merge_runs(&runs, &mut writer);
+        for run in &runs {
+            fs::remove_file(run)?;
+        }
+        count?
+    };
+    writer.flush()?;
+    Ok(count)
+}
+
+fn sort_dedup(lines: &mut Vec<Vec<u8>>) {
+    lines.sort_unstable();
+    lines.dedup();
+}
+
+// writes a sorted run to a temp file
+fn spill(lines: &mut Vec<Vec<u8>>, index: usize) -> io::Result<PathBuf> {
+    sort_dedup(lines);
+    let path =
+        std::env::temp_dir().join(format!("wordlirst-merge-{}-{}", std::process::id(), index));
+    let mut writer = BufWriter::new(File::create(&path)?);
+    for line in lines.drain(..) {
+        writer.write_all(&line)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(path)
+}
+
+// k-way merge of sorted runs, skipping lines equal to the last one written
+fn merge_runs(runs: &[PathBuf], writer: &mut impl Write) -> io::Result<usize> {
+    let mut readers = Vec::new();
+    let mut heap = BinaryHeap::new();
+    for (i, run) in runs.iter().enumerate() {
+        let mut reader = BufReader::new(File::open(run)?).split(b'\n');
+        if let Some(line) = reader.next() {
+            heap.push(Reverse((line?, i)));
+        }
+        readers.push(reader);
+    }
+
+    let mut last: Option<Vec<u8>> = None;
+    let mut count = 0;
+    while let Some(Reverse((line, i))) = heap.pop() {
+        if let Some(next) = readers[i].next() {
+            heap.push(Reverse((next?, i)));
+        }
+        if last.as_ref() == Some(&line) {
+            continue;
+        }
+        writer.write_all(&line)?;
+        writer.write_all(b"\n")?;
+        count += 1;
+        last = Some(line);
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "wordlirst-merge-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn merges_into_a_sorted_union() {
+        let a = temp_file("a", "root\nadmin\n\nadmin\r\nguest\n");
+        let b = temp_file("b", "zebra\nadmin\nroot");
+        let output = temp_file("out", "");
+        let count = merge_wordlists(&[a.clone(), b.clone()], &output).unwrap();
+        let merged = fs::read_to_string(&output).unwrap();
+        for path in [a, b, output] {
+            fs::remove_file(path).unwrap();
+        }
+        assert_eq!(merged, "admin\nguest\nroot\nzebra\n");
+        assert_eq!(count, 4);
+    }
+
+    #[test]
+    fn spilled_runs_merge_without_duplicates() {
+        let as_lines = |words: &[&str]| -> Vec<Vec<u8>> {
+            words.iter().map(|w| w.as_bytes().to_vec()).collect()
+        };
+        let runs: Vec<PathBuf> = [&["c", "a", "b", "a"][..], &["b", "d"], &["a", "e", "d"]]
+            .iter()
+            .enumerate()
+            .map(|(i, words)| spill(&mut as_lines(words), 1000 + i).unwrap())
+            .collect();
+        let mut out = Vec::new();
+        let count = merge_runs(&runs, &mut out).unwrap();
+        for run in &runs {
+            fs::remove_file(run).unwrap();
+        }
+        assert_eq!(String::from_utf8(out).unwrap(), "a\nb\nc\nd\ne\n");
+        assert_eq!(count, 5);
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+/// Time spent in each phase of a run, for `--profile`.
+///
+/// Writing happens inside the phases that generate words, its time is taken out of
+/// them so each phase is only counted once.
+#[derive(Debug, Default)]
+pub struct Profile {
+    phases: Vec<(&'static str, Duration)>,
+    // total of the nested phases so far
+    nested: Duration,
+}
+
+/// When a phase that may contain nested phases began
+#[derive(Debug, Clone, Copy)]
+pub struct Start {
+    at: Instant,
+    nested: Duration,
+}
+
+impl Profile {
+    /// Add the time since `start` to `phase`
+    pub fn record(&mut self, phase: &'static str, start: Instant) {
+        self.add(phase, start.elapsed());
+    }
+
+    /// Start timing an enclosing phase, see `record_outer`
+    pub fn start(&self) -> Start {
+        Start {
+            at: Instant::now(),
+            nested: self.nested,
+        }
+    }
+
+    /// Add the time since `start` to `phase`, less the nested phases recorded meanwhile
+    pub fn record_outer(&mut self, phase: &'static str, start: Start) {
+        let nested = self.nested - start.nested;
+        self.add(phase, start.at.elapsed().saturating_sub(nested));
+    }
+
+    /// `record` for a phase that runs inside the enclosing ones
+    pub fn record_nested(&mut self, phase: &'static str, start: Instant) {
+        let elapsed = start.elapsed();
+        self.nested += elapsed;
+        self.add(phase, elapsed);
+    }
+
+    fn add(&mut self, phase: &'static str, elapsed: Duration) {
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += elapsed,
+            None => self.phases.push((phase, elapsed)),
+        }
+    }
+
+    /// Print every phase to stderr, in the order first recorded
+    pub fn print(&self) {
+        eprintln!("Profile:");
+        for (phase, total) in &self.phases {
+            eprintln!("{:>16}: {:>10.3}ms", phase, total.as_secs_f64() * 1000.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    fn phase(profile: &Profile, name: &str) -> Duration {
+        let found = profile.phases.iter().find(|(phase, _)| *phase == name);
+        found.map(|(_, total)| *total).unwrap()
+    }
+
+    #[test]
+    fn nested_time_is_taken_out_of_the_enclosing_phase() {
+        let mut profile = Profile::default();
+        let outer = Instant::now();
+        let start = profile.start();
+        sleep(Duration::from_millis(20));
+        let write = Instant::now();
+        sleep(Duration::from_millis(60));
+        profile.record_nested("writing", write);
+        profile.record_outer("generating", start);
+        let elapsed = outer.elapsed();
+
+        let writing = phase(&profile, "writing");
+        assert!(writing >= Duration::from_millis(60));
+        let generating = phase(&profile, "generating");
+        assert!(generating >= Duration::from_millis(20));
+        // counted once between them, however slow the machine
+        assert!(
+            generating + writing <= elapsed,
+            "{:?}",
+            (generating, writing)
+        );
+        let labels: Vec<&str> = profile.phases.iter().map(|(phase, _)| *phase).collect();
+        assert_eq!(labels, ["writing", "generating"]);
+    }
+}
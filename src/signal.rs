@@ -0,0 +1,31 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether Ctrl-C was pressed since `install`
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Catch SIGINT so generation stops at the next word and buffered output gets flushed.
+/// A second Ctrl-C exits immediately.
+#[cfg(unix)]
+pub fn install() {
+    const SIGINT: i32 = 2;
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+        fn _exit(status: i32) -> !;
+    }
+    extern "C" fn on_sigint(_: i32) {
+        // only async-signal-safe calls in here
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            unsafe { _exit(130) }
+        }
+    }
+    unsafe {
+        signal(SIGINT, on_sigint);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install() {}
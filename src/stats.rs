@@ -0,0 +1,133 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// How many prefixes/suffixes to list
+const TOP: usize = 10;
+/// Length of the prefixes/suffixes counted
+const AFFIX_LENGTH: usize = 3;
+
+/// Length histogram of a wordlist, keyed by char count
+fn length_histogram(words: &[String]) -> BTreeMap<usize, usize> {
+    let mut histogram = BTreeMap::new();
+    for word in words {
+        *histogram.entry(word.chars().count()).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// Print an analysis of an existing wordlist: lengths, character classes and common affixes
+pub fn print_stats(words: &[String]) {
+    let unique: HashSet<&String> = words.iter().collect();
+    println!("Words: {} ({} unique)", words.len(), unique.len());
+
+    println!("\nLengths:");
+    for (length, count) in length_histogram(words) {
+        println!(
+            "{:>4}: {:>8} {:>6.2}%",
+            length,
+            count,
+            percent(count, words.len())
+        );
+    }
+
+    println!("\nCharsets:");
+    let mut charsets: HashMap<&str, usize> = HashMap::new();
+    for word in words {
+        *charsets.entry(charset(word)).or_insert(0) += 1;
+    }
+    for (name, count) in sorted(charsets) {
+        println!(
+            "{:>16}: {:>8} {:>6.2}%",
+            name,
+            count,
+            percent(count, words.len())
+        );
+    }
+
+    let mut prefixes: HashMap<String, usize> = HashMap::new();
+    let mut suffixes: HashMap<String, usize> = HashMap::new();
+    for word in words {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() > AFFIX_LENGTH {
+            *prefixes
+                .entry(chars[..AFFIX_LENGTH].iter().collect())
+                .or_insert(0) += 1;
+            *suffixes
+                .entry(chars[chars.len() - AFFIX_LENGTH..].iter().collect())
+                .or_insert(0) += 1;
+        }
+    }
+    for (title, affixes) in [("Prefixes", prefixes), ("Suffixes", suffixes)] {
+        println!("\nTop {}:", title);
+        for (affix, count) in sorted(affixes).into_iter().take(TOP) {
+            println!("{:>8}: {:>8}", affix, count);
+        }
+    }
+}
+
+// classes of characters a word is made of, i.e. "loweralphanum"
+fn charset(word: &str) -> &'static str {
+    let lower = word.chars().any(|c| c.is_lowercase());
+    let upper = word.chars().any(|c| c.is_uppercase());
+    let digit = word.chars().any(|c| c.is_numeric());
+    let special = word.chars().any(|c| !c.is_alphanumeric());
+    match (lower || upper, lower && upper, digit, special) {
+        (false, _, true, false) => "numeric",
+        (false, _, false, true) => "special",
+        (false, _, true, true) => "specialnum",
+        (true, false, false, false) if lower => "loweralpha",
+        (true, false, false, false) => "upperalpha",
+        (true, true, false, false) => "mixedalpha",
+        (true, false, true, false) if lower => "loweralphanum",
+        (true, false, true, false) => "upperalphanum",
+        (true, true, true, false) => "mixedalphanum",
+        (true, _, false, true) => "alphaspecial",
+        (true, _, true, true) => "all",
+        (false, _, false, false) => "empty",
+    }
+}
+
+// most common first, ties alphabetically
+fn sorted<K: Ord>(counts: HashMap<K, usize>) -> Vec<(K, usize)> {
+    let mut counts: Vec<(K, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+fn percent(count: usize, total: usize) -> f64 {
+    count as f64 * 100.0 / total.max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_counts_chars() {
+        let words: Vec<String> = ["a", "bb", "cc", "dd", "é", "abcde", "naïve"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        let histogram: Vec<(usize, usize)> = length_histogram(&words).into_iter().collect();
+        assert_eq!(histogram, [(1, 2), (2, 3), (5, 2)]);
+    }
+
+    #[test]
+    fn charsets_name_the_classes() {
+        let cases = [
+            ("abc", "loweralpha"),
+            ("ABC", "upperalpha"),
+            ("aBc", "mixedalpha"),
+            ("abc1", "loweralphanum"),
+            ("aB1", "mixedalphanum"),
+            ("123", "numeric"),
+            ("!?", "special"),
+            ("1!", "specialnum"),
+            ("a!", "alphaspecial"),
+            ("a1!", "all"),
+            ("", "empty"),
+        ];
+        for (word, name) in cases {
+            assert_eq!(charset(word), name, "{}", word);
+        }
+    }
+}
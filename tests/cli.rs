@@ -0,0 +1,653 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn wordlirst(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wordlirst"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+// a fresh path under the temp dir, unique to this test
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("wordlirst-cli-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&path);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn empty_results_fail_unless_allowed() {
+    let out = temp_path("empty.txt");
+    let out = out.to_str().unwrap();
+    // abc is too short alone and abcabc too long
+    let run = wordlirst(&["-t", "abc", "-l", "4", "5", "-o", out]);
+    assert_eq!(run.status.code(), Some(1));
+    assert!(
+        stderr(&run).contains("warning: no words were generated"),
+        "{}",
+        stderr(&run)
+    );
+
+    let run = wordlirst(&["-t", "abc", "-l", "4", "5", "-o", out, "--allow-empty"]);
+    assert!(run.status.success());
+    assert!(!stderr(&run).contains("warning"));
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn preview_prints_exactly_n_words() {
+    let out = temp_path("preview.txt");
+    let out = out.to_str().unwrap();
+    let run = wordlirst(&["-t", "admin", "-l", "1", "10", "-o", out, "--preview", "3"]);
+    assert!(run.status.success());
+    let preview: Vec<String> = stderr(&run)
+        .lines()
+        .filter_map(|line| line.strip_prefix("[preview] ").map(String::from))
+        .collect();
+    let written = std::fs::read_to_string(out).unwrap();
+    let first: Vec<&str> = written.lines().take(3).collect();
+    assert_eq!(preview, first);
+    assert!(written.lines().count() > 3);
+
+    // --preview-only stops there and writes nothing
+    std::fs::remove_file(out).unwrap();
+    let run = wordlirst(&["-t", "admin", "-o", out, "--preview", "3", "--preview-only"]);
+    assert!(run.status.success());
+    assert_eq!(stderr(&run).matches("[preview] ").count(), 3);
+    assert!(!std::path::Path::new(out).exists());
+
+    // so it doesn't need one
+    let run = wordlirst(&["-t", "admin", "--preview", "3", "--preview-only"]);
+    assert!(run.status.success(), "{}", stderr(&run));
+    assert_eq!(stderr(&run).matches("[preview] ").count(), 3);
+}
+
+#[test]
+fn max_runtime_stops_with_complete_lines() {
+    let out = temp_path("runtime.txt");
+    let out = out.to_str().unwrap();
+    // billions of words, far more than can be written in the time allowed
+    let args = ["-t", "password,letmein,dragon", "-l", "1", "40", "-o", out];
+    let run = wordlirst(&[&args[..], &["--max-runtime", "200ms"]].concat());
+    assert!(run.status.success(), "{}", stderr(&run));
+    let written = std::fs::read_to_string(out).unwrap();
+    assert!(written.ends_with('\n'));
+    assert!(written
+        .lines()
+        .all(|line| !line.is_empty() && line.len() <= 40));
+    assert!(written.lines().count() > 0);
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn long_terms_are_reported() {
+    let out = temp_path("long.txt");
+    let out = out.to_str().unwrap();
+    let run = wordlirst(&["-t", "admin,administrator", "-l", "1", "6", "-o", out]);
+    assert!(run.status.success());
+    assert!(
+        stderr(&run)
+            .contains("warning: 1 term exceeded max length 6 and was ignored: administrator"),
+        "{}",
+        stderr(&run)
+    );
+    let written = std::fs::read_to_string(out).unwrap();
+    assert!(written.lines().any(|line| line == "admin"));
+    assert!(!written.contains("administrator"));
+
+    let run = wordlirst(&[
+        "-t",
+        "admin,administrator,operator",
+        "-l",
+        "1",
+        "6",
+        "-o",
+        out,
+    ]);
+    assert!(
+        stderr(&run).contains(
+            "warning: 2 terms exceeded max length 6 and were ignored: administrator, operator"
+        ),
+        "{}",
+        stderr(&run)
+    );
+
+    let run = wordlirst(&["-t", "admin", "-l", "1", "6", "-o", out]);
+    assert!(!stderr(&run).contains("exceeded"));
+    std::fs::remove_file(out).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupt_flushes_complete_lines() {
+    let out = temp_path("interrupt.txt");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wordlirst"))
+        .args(["-t", "password,letmein,dragon", "-l", "1", "40", "-o"])
+        .arg(&out)
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    // wait for output so the handler is in place before the signal arrives
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+    while std::fs::metadata(&out).map_or(true, |meta| meta.len() == 0) {
+        if let Some(status) = child.try_wait().unwrap() {
+            panic!("exited with {} before writing anything", status);
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "nothing written in 30s"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let kill = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(kill.success());
+    let run = child.wait_with_output().unwrap();
+    assert_eq!(run.status.code(), Some(130));
+    let status = String::from_utf8(run.stdout).unwrap();
+    assert!(status.contains("Interrupted, stopped early"), "{}", status);
+    let written = std::fs::read_to_string(&out).unwrap();
+    assert!(written.ends_with('\n'));
+    assert!(written
+        .lines()
+        .all(|line| !line.is_empty() && line.len() <= 40));
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn profile_lists_each_phase() {
+    let out = temp_path("profile.txt");
+    let out = out.to_str().unwrap();
+    let run = wordlirst(&["-t", "ab,cd", "-l", "1", "6", "-o", out, "--profile"]);
+    assert!(run.status.success());
+    let report = stderr(&run);
+    let report = &report[report.find("Profile:").expect("no profile printed")..];
+    let phases: Vec<&str> = report
+        .lines()
+        .skip(1)
+        .map(|line| line.split(':').next().unwrap().trim())
+        .collect();
+    // writing is first recorded at the first word, inside the single words phase
+    assert_eq!(
+        phases,
+        [
+            "dictionary",
+            "writing",
+            "single words",
+            "concatenation",
+            "counting"
+        ]
+    );
+    assert!(report.lines().skip(1).all(|line| line.ends_with("ms")));
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn tr_rewrites_the_base_word() {
+    let out = temp_path("tr.txt");
+    let out = out.to_str().unwrap();
+    let args = ["-t", "passw0rd", "-r", "a=@", "-l", "8", "8", "-o", out];
+    let run = wordlirst(&[&args[..], &["--tr", "0:o"]].concat());
+    assert!(run.status.success(), "{}", stderr(&run));
+    let written = std::fs::read_to_string(out).unwrap();
+    let words: Vec<&str> = written.lines().collect();
+    // password is the only base, so nothing keeps the 0
+    assert_eq!(words[0], "password");
+    assert!(words.contains(&"p@ssword"));
+    assert!(words.iter().all(|w| !w.contains('0')));
+    // every casing of password and of p@ssword, whose @ has no case
+    assert_eq!(words.len(), 256 + 128);
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn tr_comes_before_the_length_check() {
+    let out = temp_path("tr-length.txt");
+    let out = out.to_str().unwrap();
+    let dict = temp_path("tr-length-dict.txt");
+    std::fs::write(&dict, "größe\n").unwrap();
+    // "größe" and "münze" are too long until they're translated
+    let args = [
+        "-t",
+        "münze",
+        "-d",
+        dict.to_str().unwrap(),
+        "-l",
+        "4",
+        "5",
+        "-o",
+        out,
+    ];
+    let run = wordlirst(&[&args[..], &["--tr", "öü:ou", "--tr", "ß:s"]].concat());
+    assert!(run.status.success(), "{}", stderr(&run));
+    assert!(!stderr(&run).contains("exceeded"), "{}", stderr(&run));
+    let written = std::fs::read_to_string(out).unwrap();
+    let words: Vec<&str> = written.lines().collect();
+    assert!(
+        words.contains(&"grose") && words.contains(&"munze"),
+        "{:?}",
+        words
+    );
+    std::fs::remove_file(out).unwrap();
+    std::fs::remove_file(dict).unwrap();
+}
+
+#[test]
+fn replacements_override_the_preset() {
+    let out = temp_path("preset.txt");
+    let out = out.to_str().unwrap();
+    let leet = |extra: &[&str]| {
+        let args = ["-t", "sat", "-l", "3", "3", "-o", out];
+        let run = wordlirst(&[&args[..], extra].concat());
+        assert!(run.status.success(), "{}", stderr(&run));
+        let written = std::fs::read_to_string(out).unwrap();
+        let mut words: Vec<String> = written
+            .lines()
+            .filter(|w| *w == w.to_lowercase() && *w != "sat")
+            .map(String::from)
+            .collect();
+        words.sort();
+        words
+    };
+    assert_eq!(leet(&["--leet-preset", "minimal"]), ["s@t"]);
+    assert_eq!(
+        leet(&["--leet-preset", "numeric"]),
+        ["547", "54t", "5a7", "5at", "s47", "s4t", "sa7"]
+    );
+    assert_eq!(leet(&["--leet-preset", "numeric", "-r", "a=@"]), ["s@t"]);
+    std::fs::remove_file(out).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn pipe_feeds_a_command_writing_the_output() {
+    let out = temp_path("pipe.txt");
+    let out = out.to_str().unwrap();
+    let args = ["-t", "ab,cd", "-l", "2", "4", "-o", out];
+    let run = wordlirst(&args);
+    assert!(run.status.success());
+    let plain = std::fs::read_to_string(out).unwrap();
+
+    let run = wordlirst(&[&args[..], &["--pipe", "cat"]].concat());
+    assert!(run.status.success(), "{}", stderr(&run));
+    assert_eq!(std::fs::read_to_string(out).unwrap(), plain);
+
+    let run = wordlirst(&[&args[..], &["--pipe", "tr a-z A-Z"]].concat());
+    assert!(run.status.success(), "{}", stderr(&run));
+    assert_eq!(std::fs::read_to_string(out).unwrap(), plain.to_uppercase());
+    std::fs::remove_file(out).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn pipe_command_that_stops_reading_ends_the_run() {
+    let out = temp_path("pipe-head.txt");
+    let out = out.to_str().unwrap();
+    // far more than a pipe buffer, so writing outlives `head`
+    let args = ["-t", "ab,cd,ef,gh", "-l", "2", "12", "-o", out];
+    let run = wordlirst(&[&args[..], &["--pipe", "head -n 3"]].concat());
+    assert!(run.status.success(), "{}", stderr(&run));
+    assert!(
+        stdout(&run).contains("Output closed, stopped early"),
+        "{}",
+        stdout(&run)
+    );
+    assert!(
+        stdout(&run).contains("3 words generated!"),
+        "{}",
+        stdout(&run)
+    );
+    assert_eq!(std::fs::read_to_string(out).unwrap().lines().count(), 3);
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn replacement_sets_are_expanded_independently() {
+    let out = temp_path("sets.txt");
+    let out = out.to_str().unwrap();
+    let words = |extra: &[&str]| {
+        let args = ["-t", "sass", "-l", "4", "4", "-o", out];
+        let run = wordlirst(&[&args[..], extra].concat());
+        assert!(run.status.success(), "{}", stderr(&run));
+        let written = std::fs::read_to_string(out).unwrap();
+        written.lines().map(String::from).collect::<Vec<_>>()
+    };
+    let sets = words(&["--replacements-set", "a=@", "--replacements-set", "s=$"]);
+    let mut union = words(&["-r", "a=@"]);
+    union.extend(words(&["-r", "s=$"]));
+    union.sort();
+    union.dedup();
+    let mut sorted = sets.clone();
+    sorted.sort();
+    // the unchanged casings come from the first set only, so nothing repeats
+    assert_eq!(sorted, union);
+    assert!(!sets.iter().any(|w| w.contains('@') && w.contains('$')));
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn years_compose_with_casing_and_leet() {
+    let out = temp_path("years.txt");
+    let out = out.to_str().unwrap();
+    let args = [
+        "-t",
+        "acme",
+        "-l",
+        "8",
+        "8",
+        "-o",
+        out,
+        "--suffix-years",
+        "2024:2025",
+    ];
+    let run = wordlirst(&args);
+    assert!(run.status.success(), "{}", stderr(&run));
+    let written = std::fs::read_to_string(out).unwrap();
+    let words: Vec<&str> = written.lines().collect();
+    for word in ["acme2024", "Acme2025", "@cm32024", "ACME2025"] {
+        assert!(words.contains(&word), "{}", word);
+    }
+    std::fs::remove_file(out).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn writes_to_a_fifo() {
+    let fifo = temp_path("fifo");
+    let made = Command::new("mkfifo").arg(&fifo).status().unwrap();
+    assert!(made.success());
+    let args = ["-t", "ab,cd", "-l", "2", "4", "-o"];
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wordlirst"))
+        .args(args)
+        .arg(&fifo)
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    // opening the read end lets the writer through, on a thread since it blocks until
+    // there's a writer, which there never is if the run fails first
+    let (sent, received) = std::sync::mpsc::channel();
+    let path = fifo.clone();
+    std::thread::spawn(move || sent.send(std::fs::read_to_string(path).unwrap()));
+    let read = match received.recv_timeout(std::time::Duration::from_secs(30)) {
+        Ok(read) => read,
+        Err(_) => {
+            child.kill().unwrap();
+            panic!("nothing came through the fifo, {}", child.wait().unwrap());
+        }
+    };
+    let run = child.wait_with_output().unwrap();
+    assert!(run.status.success(), "{}", stderr(&run));
+    std::fs::remove_file(&fifo).unwrap();
+
+    let out = temp_path("fifo.txt");
+    let file_run = wordlirst(&[&args[..], &[out.to_str().unwrap()]].concat());
+    assert!(file_run.status.success());
+    assert_eq!(read, std::fs::read_to_string(&out).unwrap());
+    std::fs::remove_file(out).unwrap();
+    // without a file to recount, the fifo's count is of the words written
+    let generated = |output: &Output| {
+        let status = String::from_utf8(output.stdout.clone()).unwrap();
+        let line = status
+            .lines()
+            .find(|line| line.ends_with("words generated!"));
+        line.unwrap().to_string()
+    };
+    assert_eq!(generated(&run), generated(&file_run));
+}
+
+#[test]
+fn leet_target_limits_the_replaced_chars() {
+    let out = temp_path("target.txt");
+    let out = out.to_str().unwrap();
+    let leet = |target: &str| {
+        let args = ["-t", "sat", "-l", "3", "3", "-r", "a=@,s=$,t=7", "-o", out];
+        let run = wordlirst(&[&args[..], &["--leet-target", target]].concat());
+        assert!(run.status.success(), "{}", stderr(&run));
+        let written = std::fs::read_to_string(out).unwrap();
+        let mut words: Vec<String> = written
+            .lines()
+            .filter(|w| *w == w.to_lowercase() && *w != "sat")
+            .map(String::from)
+            .collect();
+        words.sort();
+        words
+    };
+    assert_eq!(leet("vowels"), ["s@t"]);
+    assert_eq!(leet("consonants"), ["$a7", "$at", "sa7"]);
+    assert_eq!(leet("all").len(), 7);
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn exact_length_writes_only_that_length() {
+    let out = temp_path("exact.txt");
+    let out = out.to_str().unwrap();
+    // ß is two bytes, as is its uppercase SS
+    let args = ["-t", "ab,cd,ßab", "-o", out, "--exact-length", "4"];
+    let run = wordlirst(&args);
+    assert!(run.status.success(), "{}", stderr(&run));
+    let written = std::fs::read_to_string(out).unwrap();
+    assert!(written.lines().all(|w| w.len() == 4));
+    for word in ["abcd", "CdAb", "abab", "ßAb", "SSab"] {
+        assert!(written.lines().any(|w| w == word), "{}", word);
+    }
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn one_thread_gives_the_same_wordlist() {
+    let dict = temp_path("threads-dict.txt");
+    std::fs::write(&dict, "admin\nroot\nuser\n").unwrap();
+    let out = temp_path("threads.txt");
+    let args = [
+        "-d",
+        dict.to_str().unwrap(),
+        "-l",
+        "4",
+        "8",
+        "-o",
+        out.to_str().unwrap(),
+    ];
+    let read = |extra: &[&str]| {
+        let run = wordlirst(&[&args[..], extra].concat());
+        assert!(run.status.success(), "{}", stderr(&run));
+        std::fs::read_to_string(&out).unwrap()
+    };
+    let (all, one) = (read(&[]), read(&["--threads", "1"]));
+    assert!(!one.is_empty());
+    assert_eq!(one, all);
+    std::fs::remove_file(dict).unwrap();
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn mutations_only_leaves_out_the_base_words() {
+    let out = temp_path("mutations.txt");
+    let out = out.to_str().unwrap();
+    let args = [
+        "-t",
+        "admin,root",
+        "-l",
+        "4",
+        "10",
+        "-o",
+        out,
+        "--mutations-only",
+    ];
+    let run = wordlirst(&args);
+    assert!(run.status.success(), "{}", stderr(&run));
+    let written = std::fs::read_to_string(out).unwrap();
+    let words: Vec<&str> = written.lines().collect();
+    assert!(!words.contains(&"admin") && !words.contains(&"root"));
+    for word in ["Admin", "r00t", "adminroot", "ROOT"] {
+        assert!(words.contains(&word), "{}", word);
+    }
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn summary_only_prints_just_the_count() {
+    let out = temp_path("summary.txt");
+    let args = ["-t", "ab,administrator", "-l", "2", "2", "--summary-only"];
+    let run = wordlirst(&args);
+    assert!(run.status.success(), "{}", stderr(&run));
+    // ab, Ab, aB, AB, @b and @B, with no warning for the long term
+    assert_eq!(stdout(&run), "6\n");
+    assert_eq!(stderr(&run), "");
+
+    // an output file is neither needed nor created
+    let run = wordlirst(&[&args[..], &["-o", out.to_str().unwrap()]].concat());
+    assert_eq!(stdout(&run), "6\n");
+    assert!(!out.exists());
+}
+
+#[test]
+fn crack_reports_the_matching_word() {
+    // md5 of Adm!n, a casing and leet variant of the term
+    let hash = "B729EDCBFA9D7B93717A29AA1F8BA8F8";
+    let run = wordlirst(&[
+        "-t",
+        "admin",
+        "-l",
+        "5",
+        "5",
+        "--crack",
+        hash,
+        "--crack-algo",
+        "md5",
+    ]);
+    assert!(run.status.success(), "{}", stderr(&run));
+    let status = stdout(&run);
+    assert!(
+        status.contains(&format!("Cracked {}: Adm!n", hash)),
+        "{}",
+        status
+    );
+
+    // md5 of password
+    let miss = "5f4dcc3b5aa765d61d8327deb882cf99";
+    let run = wordlirst(&[
+        "-t",
+        "admin",
+        "-l",
+        "5",
+        "5",
+        "--crack",
+        miss,
+        "--crack-algo",
+        "md5",
+    ]);
+    assert!(stdout(&run).contains(&format!("No candidate matched {}", miss)));
+}
+
+#[test]
+fn split_by_length_writes_a_file_per_length() {
+    let dir = temp_path("split");
+    let out = temp_path("split.txt");
+    let args = ["-t", "ab,c", "-l", "1", "3"];
+    let run = wordlirst(&[&args[..], &["-o", out.to_str().unwrap()]].concat());
+    assert!(run.status.success());
+    let whole = std::fs::read_to_string(&out).unwrap();
+    std::fs::remove_file(&out).unwrap();
+
+    let run = wordlirst(&[&args[..], &["--split-by-length", dir.to_str().unwrap()]].concat());
+    assert!(run.status.success(), "{}", stderr(&run));
+    let mut names: Vec<String> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        ["wordlist-1.txt", "wordlist-2.txt", "wordlist-3.txt"]
+    );
+    for len in 1..=3 {
+        let file = std::fs::read_to_string(dir.join(format!("wordlist-{}.txt", len))).unwrap();
+        let expected: Vec<&str> = whole.lines().filter(|w| w.len() == len).collect();
+        assert_eq!(file.lines().collect::<Vec<_>>(), expected, "{}", len);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn bench_reports_throughput() {
+    let run = wordlirst(&["bench"]);
+    assert!(run.status.success(), "{}", stderr(&run));
+    let report = stdout(&run);
+    let rate = report.lines().nth(1).unwrap();
+    let (words, mb) = rate.split_once(" words/s, ").unwrap();
+    assert!(words.parse::<f64>().unwrap() > 0.0, "{}", report);
+    assert!(mb.strip_suffix(" MB/s").unwrap().parse::<f64>().is_ok());
+    assert!(report.starts_with("4667753 words, "), "{}", report);
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupt_before_any_word_is_not_an_empty_result() {
+    use std::io::BufRead;
+
+    let out = temp_path("interrupt-empty.txt");
+    // every candidate is dropped, so nothing is written however long it runs
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wordlirst"))
+        .args(["-t", "password,letmein,dragon", "-l", "1", "40"])
+        .args(["--min-unique-chars", "30", "-o"])
+        .arg(&out)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut status = std::io::BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    while !line.starts_with("Generating") {
+        line.clear();
+        assert!(status.read_line(&mut line).unwrap() > 0, "exited early");
+    }
+    // the handler goes in right after the status line
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    let kill = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(kill.success());
+    let run = child.wait_with_output().unwrap();
+    assert_eq!(run.status.code(), Some(130));
+    assert!(!stderr(&run).contains("no words were generated"));
+    assert_eq!(std::fs::read_to_string(&out).unwrap(), "");
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn count_only_needs_no_output() {
+    let run = wordlirst(&["-t", "ab", "-l", "2", "2", "--count-only"]);
+    assert!(run.status.success(), "{}", stderr(&run));
+    assert!(
+        stdout(&run).ends_with("6 words would be generated\n"),
+        "{}",
+        stdout(&run)
+    );
+}
+
+#[test]
+fn max_runtime_too_long_for_a_deadline_is_no_deadline() {
+    let out = temp_path("forever.txt");
+    let args = ["-t", "ab", "-l", "2", "2", "-o", out.to_str().unwrap()];
+    let run = wordlirst(&[&args[..], &["--max-runtime", "18446744073709551615s"]].concat());
+    assert!(run.status.success(), "{}", stderr(&run));
+    assert_eq!(std::fs::read_to_string(&out).unwrap().lines().count(), 6);
+
+    let run = wordlirst(&[&args[..], &["--max-runtime", "999999999999999999h"]].concat());
+    assert_eq!(run.status.code(), Some(2));
+    assert!(stderr(&run).contains("is too long"), "{}", stderr(&run));
+    std::fs::remove_file(out).unwrap();
+}